@@ -1,5 +1,8 @@
+use std::io::ErrorKind;
 use std::process::Command;
 
+use crate::error::{Result, ThingyError};
+
 pub const FILTER_COMPLETED: &str = r#"
     set allTodos to to dos of listToQuery
     set listTodos to {}
@@ -10,7 +13,7 @@ pub const FILTER_COMPLETED: &str = r#"
     end repeat
 "#;
 
-pub fn run_applescript(script: &str) -> Result<String, String> {
+pub fn run_applescript(script: &str) -> Result<String> {
     let result = Command::new("osascript")
         .arg("-e")
         .arg(script)
@@ -21,17 +24,10 @@ pub fn run_applescript(script: &str) -> Result<String, String> {
             if output.status.success() {
                 Ok(String::from_utf8_lossy(&output.stdout).to_string())
             } else {
-                Err(String::from_utf8_lossy(&output.stderr).to_string())
+                Err(ThingyError::from_osascript(&String::from_utf8_lossy(&output.stderr)))
             }
         }
-        Err(e) => Err(format!("Error executing osascript: {}", e)),
-    }
-}
-
-pub fn parse_list_name(name: &str) -> Result<&'static str, String> {
-    match name.to_lowercase().as_str() {
-        "inbox" => Ok("Inbox"),
-        "today" => Ok("Today"),
-        _ => Err(format!("Unknown list '{}'. Valid lists: inbox, today", name)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(ThingyError::ThingsNotInstalled),
+        Err(e) => Err(ThingyError::Other(format!("Error executing osascript: {}", e))),
     }
 }
@@ -0,0 +1,218 @@
+use std::env;
+use std::path::PathBuf;
+
+use chrono::Duration;
+
+use crate::cache::{self, CachedBackend};
+use crate::config::{self, ReadSource, WriteMethod};
+use crate::error::{Result, ThingyError};
+use crate::file_backend::FileBackend;
+use crate::journal::{Journal, Snapshot};
+use crate::lists::ListInfo;
+use crate::registry::RegistryStore;
+use crate::tags::TagInfo;
+use crate::things::ThingsBackend;
+use crate::things_db::{self, ThingsDbBackend};
+use crate::things_url::ThingsUrlBackend;
+use crate::todo::{NewTodo, Todo};
+
+/// Storage for todos.  Every command goes through one of these, so the
+/// CLI can run against Things3 on a Mac or against a plain JSON file
+/// anywhere else.
+///
+/// A list name is a built-in list, area or project, exactly as named
+/// (see `lists::resolve_list`).
+///
+/// Mutations address todos by their stable `Todo::id`, never by position,
+/// so a list changing between fetch and update can't redirect them.
+pub trait Backend {
+    /// Open todos in `list_name`, in display order.
+    fn fetch_todos(&self, list_name: &str) -> Result<Vec<Todo>>;
+
+    /// Completed (but not yet logged) todos in `list_name`.
+    fn fetch_completed(&self, list_name: &str) -> Result<Vec<Todo>>;
+
+    /// Open and completed todos in `list_name`, in display order.
+    fn fetch_all(&self, list_name: &str) -> Result<Vec<Todo>>;
+
+    /// Todos completed within the last `days` days, most recent first.
+    fn fetch_logbook(&self, days: u32) -> Result<Vec<Todo>>;
+
+    fn fetch_notes(&self, id: &str) -> Result<String>;
+
+    /// The user's areas and open projects (not the built-in lists).
+    fn fetch_lists(&self) -> Result<Vec<ListInfo>>;
+
+    fn count_todos(&self, list_name: &str) -> Result<usize> {
+        self.fetch_todos(list_name).map(|todos| todos.len())
+    }
+
+    /// Creates `todo` in `list_name` (a project, if `todo.heading` is
+    /// set).  Returns the name of the new todo.
+    fn add_todo(&self, list_name: &str, todo: &NewTodo) -> Result<String>;
+
+    /// Returns the name of the new project.
+    fn create_project(&self, name: &str, area: Option<&str>) -> Result<String>;
+
+    /// Marks a project, and any todos still open in it, complete.
+    fn complete_project(&self, id: &str) -> Result<String>;
+
+    /// Moves a project and its todos to the Trash.
+    fn delete_project(&self, id: &str) -> Result<String>;
+
+    /// Marks a todo complete, dropping the configured in-progress tag.
+    /// Returns its name.
+    fn complete_todo(&self, id: &str) -> Result<String>;
+
+    fn delete_todo(&self, id: &str) -> Result<String>;
+
+    fn move_todo(&self, id: &str, to_list: &str) -> Result<String>;
+
+    /// Adds the `add` tags to a todo (creating any that don't exist yet)
+    /// and removes the `remove` tags.  Returns the todo's name.
+    fn edit_tags(&self, id: &str, add: &[&str], remove: &[&str]) -> Result<String>;
+
+    fn add_tag(&self, id: &str, tag: &str) -> Result<String> {
+        self.edit_tags(id, &[tag], &[])
+    }
+
+    /// Every tag, with its parent and number of open todos.
+    fn fetch_tags(&self) -> Result<Vec<TagInfo>>;
+
+    /// Schedules a todo for tomorrow.  Returns its name.
+    fn defer_todo(&self, id: &str) -> Result<String>;
+
+    /// Completing also drops the in-progress tag.
+    fn set_completed(&self, id: &str, completed: bool) -> Result<()>;
+
+    /// Adds `tag` if absent, removes it if present.  Returns the todo's
+    /// new tags.
+    fn toggle_tag(&self, id: &str, tag: &str) -> Result<Vec<String>>;
+
+    /// Moves completed todos to the Logbook.
+    fn log_completed(&self) -> Result<()>;
+
+    /// Puts a todo back as `before` describes, bringing it back out of
+    /// the Trash or Logbook if need be.  Returns its name.
+    fn restore_todo(&self, id: &str, before: &Snapshot) -> Result<String>;
+
+    /// Applies `ops` in order, in one round trip where the backend can,
+    /// returning each op's todo name or error.  The name is empty for a
+    /// change handed off without confirmation, as by URL.  A failed op
+    /// doesn't stop the rest.
+    fn apply(&self, ops: &[Op]) -> Vec<Result<String>> {
+        ops.iter().map(|op| apply_one(self, op)).collect()
+    }
+
+    /// The script `apply` would run for `ops`, for backends that run
+    /// scripts.
+    fn script(&self, _ops: &[Op]) -> Option<String> {
+        None
+    }
+
+    /// Drops any cached data, so the next fetches go to the source.
+    fn refresh(&self) {}
+
+    /// Where changes to this backend's todos are journaled for `thingy
+    /// undo`.
+    fn journal(&self) -> Journal {
+        Journal::from_env()
+    }
+
+    /// Where the identifiers given to this backend's todos are kept.
+    fn registry(&self) -> RegistryStore {
+        RegistryStore::from_env()
+    }
+}
+
+/// A change to one todo, for `Backend::apply`.  `Complete` carries the
+/// todo's tags as last fetched, for backends that can't remove a tag
+/// without knowing it's there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Complete { id: String, tags: Vec<String> },
+    Delete { id: String },
+    Move { id: String, to_list: String },
+    Defer { id: String },
+    EditTags { id: String, add: Vec<String>, remove: Vec<String> },
+    Restore { id: String, before: Snapshot },
+}
+
+impl Op {
+    pub fn id(&self) -> &str {
+        match self {
+            Op::Complete { id, .. }
+            | Op::Delete { id }
+            | Op::Move { id, .. }
+            | Op::Defer { id }
+            | Op::EditTags { id, .. }
+            | Op::Restore { id, .. } => id,
+        }
+    }
+}
+
+/// Applies `op` through the backend's single-todo methods.
+pub fn apply_one<B: Backend + ?Sized>(backend: &B, op: &Op) -> Result<String> {
+    match op {
+        Op::Complete { id, .. } => backend.complete_todo(id),
+        Op::Delete { id } => backend.delete_todo(id),
+        Op::Move { id, to_list } => backend.move_todo(id, to_list),
+        Op::Defer { id } => backend.defer_todo(id),
+        Op::EditTags { id, add, remove } => {
+            let add: Vec<&str> = add.iter().map(|t| t.as_str()).collect();
+            let remove: Vec<&str> = remove.iter().map(|t| t.as_str()).collect();
+            backend.edit_tags(id, &add, &remove)
+        }
+        Op::Restore { id, before } => backend.restore_todo(id, before),
+    }
+}
+
+/// Picks the backend named by `THINGY_BACKEND` ("things", the default, or
+/// "file").  The file backend reads `THINGY_FILE`, falling back to
+/// `~/.thingy.json`.  Things3 is read through AppleScript, cached for the
+/// configured `cache_ttl`, or from its database (see `things_backend`).
+pub fn from_env() -> Result<Box<dyn Backend>> {
+    from_env_caching(cache::max_age())
+}
+
+/// `from_env`, reusing Things3 data up to `max_age` old.
+pub(crate) fn from_env_caching(max_age: Duration) -> Result<Box<dyn Backend>> {
+    match env::var("THINGY_BACKEND").unwrap_or_default().to_lowercase().as_str() {
+        "" | "things" => things_backend(max_age),
+        "file" => Ok(Box::new(FileBackend::open(default_file_path())?)),
+        other => Err(ThingyError::usage(
+            format!("Unknown backend '{}'. Valid backends: things, file", other),
+            None,
+        )),
+    }
+}
+
+/// Things3, read from its database if `things.reads` says so, else
+/// through AppleScript with reads cached, and changed through AppleScript
+/// or, if `things.writes` says so, URLs.
+fn things_backend(max_age: Duration) -> Result<Box<dyn Backend>> {
+    let things = &config::get().things;
+    let writes: Box<dyn Backend> = match things.writes {
+        WriteMethod::AppleScript => Box::new(ThingsBackend),
+        WriteMethod::Url => Box::new(ThingsUrlBackend::new(Box::new(ThingsBackend), &things.auth_token)),
+    };
+    match things.reads {
+        ReadSource::AppleScript => Ok(Box::new(CachedBackend::new(writes, max_age))),
+        ReadSource::Database => {
+            let path = if things.database.is_empty() {
+                things_db::default_path()?
+            } else {
+                PathBuf::from(&things.database)
+            };
+            Ok(Box::new(ThingsDbBackend::open(&path, writes)?))
+        }
+    }
+}
+
+fn default_file_path() -> PathBuf {
+    if let Ok(path) = env::var("THINGY_FILE") {
+        return PathBuf::from(path);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".thingy.json")
+}
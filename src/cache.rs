@@ -0,0 +1,344 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{Backend, Op};
+use crate::config;
+use crate::error::Result;
+use crate::journal::{Journal, Snapshot};
+use crate::lists::ListInfo;
+use crate::registry::{write_replacing, RegistryStore};
+use crate::tags::TagInfo;
+use crate::todo::{NewTodo, Todo};
+
+/// Lists, todos and tags as last fetched from the backend, each with
+/// when it was fetched.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Cache {
+    #[serde(default)]
+    lists: Option<Cached<Vec<ListInfo>>>,
+    /// Open todos, by list name.
+    #[serde(default)]
+    todos: BTreeMap<String, Cached<Vec<Todo>>>,
+    #[serde(default)]
+    tags: Option<Cached<Vec<TagInfo>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cached<T> {
+    fetched: NaiveDateTime,
+    value: T,
+}
+
+impl<T> Cached<T> {
+    fn new(value: T) -> Self {
+        Cached {
+            fetched: Local::now().naive_local(),
+            value,
+        }
+    }
+
+    fn fresh(&self, max_age: Duration) -> bool {
+        Local::now().naive_local() - self.fetched < max_age
+    }
+}
+
+/// How long data is reused by default: the configured `cache_ttl`.
+pub fn max_age() -> Duration {
+    i64::try_from(config::get().cache_ttl_secs())
+        .ok()
+        .and_then(Duration::try_seconds)
+        .unwrap_or(Duration::MAX)
+}
+
+/// A backend whose reads of lists, todos and tags are answered from a
+/// snapshot saved between runs while it's younger than `max_age`.  Every
+/// change made through it drops the snapshot, as does `refresh`.
+pub struct CachedBackend {
+    inner: Box<dyn Backend>,
+    max_age: Duration,
+    path: PathBuf,
+    cache: RefCell<Cache>,
+}
+
+impl CachedBackend {
+    /// Keeps the snapshot at `$THINGY_CACHE`, else `~/.thingy-cache.json`.
+    pub fn new(inner: Box<dyn Backend>, max_age: Duration) -> Self {
+        CachedBackend::with_file(inner, max_age, cache_path())
+    }
+
+    /// Keeps the snapshot at `path`.
+    pub fn with_file(inner: Box<dyn Backend>, max_age: Duration, path: PathBuf) -> Self {
+        CachedBackend {
+            inner,
+            max_age,
+            cache: RefCell::new(load(&path)),
+            path,
+        }
+    }
+
+    fn invalidate(&self) {
+        *self.cache.borrow_mut() = Cache::default();
+        save(&self.path, &self.cache.borrow());
+    }
+
+    /// `result` of a change, after dropping the cache: even a failed
+    /// change may have done part of its work.
+    fn changed<T>(&self, result: T) -> T {
+        self.invalidate();
+        result
+    }
+}
+
+fn cache_path() -> PathBuf {
+    if let Ok(path) = env::var("THINGY_CACHE") {
+        return PathBuf::from(path);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".thingy-cache.json")
+}
+
+/// The cache saved at `path`.  It only saves time, so one that can't be
+/// read (or written, in `save`) is ignored.
+fn load(path: &Path) -> Cache {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, cache: &Cache) {
+    if let Ok(json) = serde_json::to_string(cache) {
+        let _ = write_replacing(path, &json);
+    }
+}
+
+impl Backend for CachedBackend {
+    fn fetch_todos(&self, list_name: &str) -> Result<Vec<Todo>> {
+        if let Some(cached) = self.cache.borrow().todos.get(list_name) {
+            if cached.fresh(self.max_age) {
+                return Ok(cached.value.clone());
+            }
+        }
+        let todos = self.inner.fetch_todos(list_name)?;
+        let mut cache = self.cache.borrow_mut();
+        cache.todos.insert(list_name.to_string(), Cached::new(todos.clone()));
+        save(&self.path, &cache);
+        Ok(todos)
+    }
+
+    fn fetch_completed(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.inner.fetch_completed(list_name)
+    }
+
+    fn fetch_all(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.inner.fetch_all(list_name)
+    }
+
+    fn fetch_logbook(&self, days: u32) -> Result<Vec<Todo>> {
+        self.inner.fetch_logbook(days)
+    }
+
+    fn fetch_notes(&self, id: &str) -> Result<String> {
+        self.inner.fetch_notes(id)
+    }
+
+    fn fetch_lists(&self) -> Result<Vec<ListInfo>> {
+        if let Some(cached) = &self.cache.borrow().lists {
+            if cached.fresh(self.max_age) {
+                return Ok(cached.value.clone());
+            }
+        }
+        let lists = self.inner.fetch_lists()?;
+        let mut cache = self.cache.borrow_mut();
+        cache.lists = Some(Cached::new(lists.clone()));
+        save(&self.path, &cache);
+        Ok(lists)
+    }
+
+    /// Counted from fresh cached todos, else by `inner`, which may count
+    /// without fetching them.
+    fn count_todos(&self, list_name: &str) -> Result<usize> {
+        if let Some(cached) = self.cache.borrow().todos.get(list_name) {
+            if cached.fresh(self.max_age) {
+                return Ok(cached.value.len());
+            }
+        }
+        self.inner.count_todos(list_name)
+    }
+
+    fn fetch_tags(&self) -> Result<Vec<TagInfo>> {
+        if let Some(cached) = &self.cache.borrow().tags {
+            if cached.fresh(self.max_age) {
+                return Ok(cached.value.clone());
+            }
+        }
+        let tags = self.inner.fetch_tags()?;
+        let mut cache = self.cache.borrow_mut();
+        cache.tags = Some(Cached::new(tags.clone()));
+        save(&self.path, &cache);
+        Ok(tags)
+    }
+
+    fn add_todo(&self, list_name: &str, todo: &NewTodo) -> Result<String> {
+        self.changed(self.inner.add_todo(list_name, todo))
+    }
+
+    fn create_project(&self, name: &str, area: Option<&str>) -> Result<String> {
+        self.changed(self.inner.create_project(name, area))
+    }
+
+    fn complete_project(&self, id: &str) -> Result<String> {
+        self.changed(self.inner.complete_project(id))
+    }
+
+    fn delete_project(&self, id: &str) -> Result<String> {
+        self.changed(self.inner.delete_project(id))
+    }
+
+    fn complete_todo(&self, id: &str) -> Result<String> {
+        self.changed(self.inner.complete_todo(id))
+    }
+
+    fn delete_todo(&self, id: &str) -> Result<String> {
+        self.changed(self.inner.delete_todo(id))
+    }
+
+    fn move_todo(&self, id: &str, to_list: &str) -> Result<String> {
+        self.changed(self.inner.move_todo(id, to_list))
+    }
+
+    fn edit_tags(&self, id: &str, add: &[&str], remove: &[&str]) -> Result<String> {
+        self.changed(self.inner.edit_tags(id, add, remove))
+    }
+
+    fn defer_todo(&self, id: &str) -> Result<String> {
+        self.changed(self.inner.defer_todo(id))
+    }
+
+    fn set_completed(&self, id: &str, completed: bool) -> Result<()> {
+        self.changed(self.inner.set_completed(id, completed))
+    }
+
+    fn toggle_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        self.changed(self.inner.toggle_tag(id, tag))
+    }
+
+    fn log_completed(&self) -> Result<()> {
+        self.changed(self.inner.log_completed())
+    }
+
+    fn restore_todo(&self, id: &str, before: &Snapshot) -> Result<String> {
+        self.changed(self.inner.restore_todo(id, before))
+    }
+
+    fn apply(&self, ops: &[Op]) -> Vec<Result<String>> {
+        self.changed(self.inner.apply(ops))
+    }
+
+    fn script(&self, ops: &[Op]) -> Option<String> {
+        self.inner.script(ops)
+    }
+
+    fn refresh(&self) {
+        self.invalidate();
+    }
+
+    fn journal(&self) -> Journal {
+        self.inner.journal()
+    }
+
+    fn registry(&self) -> RegistryStore {
+        self.inner.registry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_backend::FileBackend;
+
+    /// A cache file of its own for the test `name`, removed first.
+    fn cache_file(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("thingy-cache-{}-{}.json", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn cached(name: &str, max_age: Duration) -> CachedBackend {
+        let inner = FileBackend::in_memory();
+        inner.add_todo("Today", &NewTodo::new("Buy milk")).unwrap();
+        CachedBackend::with_file(Box::new(inner), max_age, cache_file(name))
+    }
+
+    /// Makes the cache say Today is empty, as if a todo had been added to
+    /// Things3 since it was fetched.
+    fn outdate(backend: &CachedBackend) {
+        let mut cache = backend.cache.borrow_mut();
+        cache.todos.insert("Today".to_string(), Cached::new(Vec::new()));
+    }
+
+    fn count(backend: &dyn Backend) -> usize {
+        backend.fetch_todos("Today").unwrap().len()
+    }
+
+    #[test]
+    fn test_fresh_data_is_reused() {
+        let backend = cached("fresh", Duration::minutes(1));
+        assert_eq!(count(&backend), 1);
+        outdate(&backend);
+        assert_eq!(count(&backend), 0);
+        assert_eq!(backend.count_todos("Today").unwrap(), 0);
+
+        backend.refresh();
+        assert_eq!(backend.count_todos("Today").unwrap(), 1);
+        assert_eq!(count(&backend), 1);
+        fs::remove_file(&backend.path).unwrap();
+    }
+
+    #[test]
+    fn test_stale_data_is_fetched_again() {
+        let backend = cached("stale", Duration::zero());
+        outdate(&backend);
+        assert_eq!(count(&backend), 1);
+        outdate(&backend);
+        assert_eq!(backend.count_todos("Today").unwrap(), 1);
+        fs::remove_file(&backend.path).unwrap();
+    }
+
+    #[test]
+    fn test_changes_invalidate() {
+        let backend = cached("changes", Duration::minutes(1));
+        assert_eq!(count(&backend), 1);
+        backend.add_todo("Today", &NewTodo::new("Call mom")).unwrap();
+        assert_eq!(count(&backend), 2);
+
+        let id = backend.fetch_todos("Today").unwrap()[0].id.clone();
+        backend.apply(&[Op::Complete { id, tags: Vec::new() }]);
+        assert_eq!(count(&backend), 1);
+        fs::remove_file(&backend.path).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_saved_between_runs() {
+        let backend = cached("runs", Duration::minutes(1));
+        assert_eq!(count(&backend), 1);
+
+        // A new run reads the snapshot rather than its (empty) backend:
+        let path = backend.path.clone();
+        let empty = Box::new(FileBackend::in_memory());
+        let next = CachedBackend::with_file(empty, Duration::minutes(1), path.clone());
+        let todos = next.fetch_todos("Today").unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].name, "Buy milk");
+
+        next.refresh();
+        assert_eq!(count(&next), 0);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,588 @@
+use std::io;
+use std::iter;
+
+use chrono::Duration;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::commands::*;
+use crate::error::{Result, ThingyError};
+use crate::interactive::interactive_mode;
+use crate::output::Format;
+use crate::{backend, cache, completion, config};
+
+/// Manage Things3 todos from the command line.
+#[derive(Debug, Parser)]
+#[command(
+    name = "thingy",
+    version,
+    after_help = "With no command, shows the default list (Today unless configured).\n\
+                  Views from the config file run as commands, e.g. 'thingy quick'."
+)]
+struct Cli {
+    /// Print listings as JSON
+    #[arg(long, global = true, conflicts_with = "format")]
+    json: bool,
+
+    /// Print listings as text (default), json or tsv
+    #[arg(long, global = true, value_name = "FMT")]
+    format: Option<Format>,
+
+    /// Show what a command would change, without changing it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Like --dry-run, also printing the script it would run
+    #[arg(long, global = true)]
+    show_script: bool,
+
+    /// Fetch fresh data from Things3 instead of using the cache
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Add a new todo (to the default list)
+    #[command(
+        long_about = "Add a new todo (to the default list).\n\n\
+                      The text may include #tag, @when, !deadline, -- notes and \
+                      --checklist \"a;b\"; see the README for details."
+    )]
+    Add {
+        /// Add the todo under this heading of a project
+        #[arg(long)]
+        heading: Option<String>,
+
+        /// An optional list, area or project, then the todo text
+        #[arg(value_name = "TEXT", required = true, allow_hyphen_values = true)]
+        text: Vec<String>,
+    },
+
+    /// Show current inbox todos
+    Inbox,
+
+    /// Show current today todos
+    Today,
+
+    /// Show todos in any list, area or project
+    Ls {
+        list: Option<String>,
+
+        /// Only show todos matching a query, e.g. 'tag:errand and due<3d'
+        #[arg(long = "where", value_name = "QUERY")]
+        filter: Option<String>,
+
+        /// Sort by comma-separated keys, e.g. time,name
+        #[arg(long, value_name = "KEYS")]
+        sort: Option<String>,
+    },
+
+    /// Show all lists, areas and projects
+    Lists,
+
+    /// Show and manage projects
+    #[command(visible_alias = "projects")]
+    Project {
+        #[command(subcommand)]
+        command: Option<ProjectCommand>,
+    },
+
+    /// Show areas
+    #[command(visible_alias = "areas")]
+    Area {
+        #[command(subcommand)]
+        command: Option<AreaCommand>,
+    },
+
+    /// Show in-progress todos from the default list
+    Inprog,
+
+    /// Show completed todos from the default list
+    #[command(visible_alias = "finished")]
+    Completed,
+
+    /// Show count of open todos in the default list
+    #[command(visible_alias = "total")]
+    Count,
+
+    /// Remove todos
+    Rm(Targets),
+
+    /// Mark todos complete
+    #[command(visible_aliases = ["done", "finish"])]
+    Complete(Targets),
+
+    /// Move todos between lists (to Today by default)
+    #[command(visible_alias = "move")]
+    Mv {
+        /// An optional list to move from, identifiers, and an optional
+        /// list to move to (defaults to Today)
+        #[arg(value_name = "ARGS", required = true)]
+        args: Vec<String>,
+    },
+
+    /// Tag todos as in-progress
+    Workon(Targets),
+
+    /// Mark a random todo from the default list in-progress
+    Rand,
+
+    /// Tag todos as on-deck, or show the on-deck todo
+    #[command(visible_alias = "ondeck")]
+    Next {
+        /// An optional list, area or project, then identifiers
+        #[arg(value_name = "ID")]
+        args: Vec<String>,
+    },
+
+    /// Show the views defined in the config file
+    Views,
+
+    /// Add tags to todos
+    Tag(TagArgs),
+
+    /// Remove tags from todos
+    Untag(TagArgs),
+
+    /// Show all tags with open todo counts
+    Tags,
+
+    /// Show notes for a todo
+    #[command(visible_alias = "view")]
+    Show {
+        /// An optional list, area or project, then an identifier
+        #[arg(value_name = "ID", required = true, num_args = 1..=2)]
+        args: Vec<String>,
+    },
+
+    /// Show logbook entries
+    Log {
+        /// How many days back to show
+        #[arg(default_value_t = 1, value_parser = positive)]
+        days: u32,
+    },
+
+    /// Defer todos to tomorrow
+    Defer(Targets),
+
+    /// Show all untagged todos
+    Untagged,
+
+    /// Show the todo with the shortest time tag
+    Soonest,
+
+    /// Undo the last changes to todos (adding todos and changes to
+    /// projects can't be undone)
+    Undo {
+        /// How many changes to undo
+        #[arg(default_value_t = 1, value_parser = positive)]
+        count: u32,
+    },
+
+    /// Show recent changes, most recent first
+    History,
+
+    /// Interactive mode with keyboard navigation
+    #[command(visible_alias = "i")]
+    Interactive,
+
+    /// Print a shell completion script
+    #[command(
+        long_about = "Print a shell completion script, e.g.\n\n    \
+                      thingy completions zsh > ~/.zfunc/_thingy"
+    )]
+    Completions { shell: Shell },
+
+    #[command(external_subcommand)]
+    View(Vec<String>),
+}
+
+/// Todos to act on.
+#[derive(Debug, Args)]
+struct Targets {
+    /// An optional list, area or project, then identifiers, numbers or
+    /// names
+    #[arg(value_name = "ID", required = true)]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+struct TagArgs {
+    /// An optional list, area or project, identifiers, then tags (use
+    /// #tag if a tag looks like an identifier)
+    #[arg(value_name = "ID|TAG", required = true, num_args = 2..)]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ProjectCommand {
+    /// Show open projects with identifiers
+    Ls,
+
+    /// Create a project
+    New {
+        /// Put the project in this area
+        #[arg(long)]
+        area: Option<String>,
+
+        #[arg(required = true)]
+        name: Vec<String>,
+    },
+
+    /// Show a project's todos
+    Show { id: String },
+
+    /// Complete a project and its todos
+    Done { id: String },
+
+    /// Remove a project and its todos
+    Rm { id: String },
+}
+
+#[derive(Debug, Subcommand)]
+enum AreaCommand {
+    /// Show areas with identifiers
+    Ls,
+
+    /// Show an area's projects and todos
+    Show { id: String },
+}
+
+impl Cli {
+    fn format(&self) -> Format {
+        if self.json {
+            Format::Json
+        } else {
+            self.format.unwrap_or(Format::Text)
+        }
+    }
+
+    fn mode(&self) -> Mode {
+        if self.show_script {
+            Mode::ShowScript
+        } else if self.dry_run {
+            Mode::DryRun
+        } else {
+            Mode::Apply
+        }
+    }
+}
+
+impl Command {
+    /// The name of the command, if it changes things without planning
+    /// its changes as `Op`s first, so can't be dry run.
+    fn lacks_dry_run(&self) -> Option<&'static str> {
+        match self {
+            Command::Add { .. } => Some("add"),
+            Command::Rand => Some("rand"),
+            Command::Interactive => Some("interactive"),
+            Command::Project { command: Some(command) } => match command {
+                ProjectCommand::New { .. } => Some("project new"),
+                ProjectCommand::Done { .. } => Some("project done"),
+                ProjectCommand::Rm { .. } => Some("project rm"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The name of the command, if it shows something other than
+    /// listings, so has no JSON or TSV output.
+    fn lacks_format(&self) -> Option<&'static str> {
+        match self {
+            Command::Show { .. } => Some("show"),
+            Command::Views => Some("views"),
+            _ => None,
+        }
+    }
+}
+
+/// The command-line grammar.
+pub(crate) fn command() -> clap::Command {
+    Cli::command()
+}
+
+/// Whether `name` is a command or an alias of one, so a view by that name
+/// could never be run.
+pub(crate) fn is_command(name: &str) -> bool {
+    let mut cli = command();
+    cli.build();
+    let found = cli
+        .get_subcommands()
+        .any(|command| command.get_name() == name || command.get_all_aliases().any(|alias| alias == name));
+    found
+}
+
+/// Runs the command line `args` (without the program name).
+pub fn run(args: Vec<String>) -> Result<()> {
+    if args.first().is_some_and(|arg| arg == "__complete") {
+        return complete_words(&args[1..]);
+    }
+
+    let cli = match Cli::try_parse_from(iter::once("thingy".to_string()).chain(args)) {
+        Ok(cli) => cli,
+        // --help and --version "fail" with the text to show:
+        Err(error) if !error.use_stderr() => {
+            print!("{}", error);
+            return Ok(());
+        }
+        Err(error) => return Err(usage_error(&error)),
+    };
+    let format = cli.format();
+    let mode = cli.mode();
+
+    let Some(command) = cli.command else {
+        config::init().map_err(ThingyError::Parse)?;
+        let backend = backend::from_env()?;
+        if cli.refresh {
+            backend.refresh();
+        }
+        return show_default_list(backend.as_ref(), format);
+    };
+    if let Command::Completions { shell } = command {
+        match completion::script(&shell.to_string()) {
+            Some(script) => print!("{}", script),
+            None => clap_complete::generate(shell, &mut Cli::command(), "thingy", &mut io::stdout()),
+        }
+        return Ok(());
+    }
+    if let Command::Add { text, .. } = &command {
+        check_add_text(text)?;
+    }
+    if let (Some(name), true) = (command.lacks_dry_run(), mode != Mode::Apply) {
+        return Err(ThingyError::usage(format!("'{}' doesn't support --dry-run", name), None));
+    }
+    if let (Some(name), true) = (command.lacks_format(), format != Format::Text) {
+        return Err(ThingyError::usage(format!("'{}' doesn't support --json or --format", name), None));
+    }
+    config::init().map_err(ThingyError::Parse)?;
+
+    let backend = backend::from_env()?;
+    let backend = backend.as_ref();
+    if cli.refresh {
+        backend.refresh();
+    }
+
+    match command {
+        Command::Add { heading, text } => add_todo(backend, &text, heading),
+        Command::Inbox => show_inbox(backend, format),
+        Command::Today => show_today(backend, format),
+        Command::Ls { list, filter, sort } => list_todos(
+            backend,
+            list.as_deref(),
+            filter.as_deref(),
+            sort.as_deref(),
+            format,
+        ),
+        Command::Lists => show_lists(backend, format),
+        Command::Project { command } => match command.unwrap_or(ProjectCommand::Ls) {
+            ProjectCommand::Ls => show_projects(backend, format),
+            ProjectCommand::New { area, name } => {
+                new_project(backend, &name.join(" "), area.as_deref())
+            }
+            ProjectCommand::Show { id } => show_project(backend, &id, format),
+            ProjectCommand::Done { id } => complete_project(backend, &id),
+            ProjectCommand::Rm { id } => remove_project(backend, &id),
+        },
+        Command::Area { command } => match command.unwrap_or(AreaCommand::Ls) {
+            AreaCommand::Ls => show_areas(backend, format),
+            AreaCommand::Show { id } => show_area(backend, &id, format),
+        },
+        Command::Inprog => show_inprog(backend, format),
+        Command::Completed => show_completed(backend, format),
+        Command::Count => count_todos(backend, format),
+        Command::Rm(targets) => remove_todo(backend, &targets.args, mode),
+        Command::Complete(targets) => complete_todo(backend, &targets.args, mode),
+        Command::Mv { args } => move_todo(backend, &args, mode),
+        Command::Workon(targets) => workon_todo(backend, &targets.args, mode),
+        Command::Rand => rand_todo(backend),
+        Command::Next { args } => next_todo(backend, &args, format, mode),
+        Command::Views => {
+            show_views();
+            Ok(())
+        }
+        Command::Tag(tags) => tag_todos(backend, &tags.args, mode),
+        Command::Untag(tags) => untag_todos(backend, &tags.args, mode),
+        Command::Tags => show_tags(backend, format),
+        Command::Show { args } => show_todo_notes(backend, &args),
+        Command::Log { days } => show_log(backend, days, format),
+        Command::Defer(targets) => defer_todo(backend, &targets.args, mode),
+        Command::Untagged => show_untagged(backend, format),
+        Command::Soonest => soonest_todo(backend, format),
+        Command::Undo { count } => undo_command(backend, count as usize, mode),
+        Command::History => show_history(backend, format),
+        Command::Interactive => interactive_mode(backend),
+        Command::Completions { .. } => unreachable!(),
+        Command::View(args) => {
+            if run_view(backend, &args[0], format)? {
+                Ok(())
+            } else {
+                Err(ThingyError::usage(
+                    format!("Unknown command '{}'", args[0]),
+                    "thingy [OPTIONS] [COMMAND] (see 'thingy help')".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+/// Rejects a global option in `add`'s text, which would otherwise end up
+/// in the todo's name.  Notes, after `--`, may say anything.
+fn check_add_text(text: &[String]) -> Result<()> {
+    let cli = command();
+    let global_flags: Vec<String> = cli
+        .get_arguments()
+        .filter(|arg| arg.is_global_set())
+        .filter_map(|arg| arg.get_long())
+        .map(|long| format!("--{}", long))
+        .collect();
+    let misplaced = text
+        .iter()
+        .take_while(|word| *word != "--")
+        .find(|word| global_flags.iter().any(|flag| word.split('=').next() == Some(flag)));
+    match misplaced {
+        Some(flag) => Err(ThingyError::usage(
+            format!("'{}' must come before the todo text", flag.split('=').next().unwrap_or(flag)),
+            "thingy [OPTIONS] add [LIST] <TEXT>...".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Prints completions for `words` (see `completion`).  Failing quietly
+/// is better than spilling errors over the command line.
+fn complete_words(words: &[String]) -> Result<()> {
+    let _ = config::init();
+    // Completions may be a little older than listings:
+    let max_age = cache::max_age().max(Duration::minutes(5));
+    let backend = backend::from_env_caching(max_age)?;
+    let candidates = completion::complete(command(), backend.as_ref(), words);
+    for candidate in candidates {
+        println!("{}\t{}", candidate.value, candidate.description);
+    }
+    Ok(())
+}
+
+fn positive(arg: &str) -> std::result::Result<u32, String> {
+    match arg.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err("must be a positive number".to_string()),
+    }
+}
+
+/// Turns a command-line error from clap into one of ours, reported like
+/// any other.
+fn usage_error(error: &clap::Error) -> ThingyError {
+    let rendered = error.render().to_string();
+    let message: Vec<&str> = rendered.lines().take_while(|line| !line.is_empty()).collect();
+    let message = message.join("\n");
+    let usage = rendered
+        .lines()
+        .find_map(|line| line.strip_prefix("Usage: "))
+        .map(|usage| format!("{} (see 'thingy help')", usage));
+    ThingyError::usage(message.trim_start_matches("error: "), usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Cli, clap::Error> {
+        Cli::try_parse_from(iter::once("thingy").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_global_flags_anywhere() {
+        let cli = parse(&["today", "--json"]).unwrap();
+        assert_eq!(cli.format(), Format::Json);
+        let cli = parse(&["--format", "tsv", "log", "3"]).unwrap();
+        assert_eq!(cli.format(), Format::Tsv);
+        assert!(matches!(cli.command, Some(Command::Log { days: 3 })));
+        assert_eq!(parse(&["inbox"]).unwrap().format(), Format::Text);
+        assert!(parse(&["--format=xml"]).is_err());
+
+        let cli = parse(&["--dry-run", "rm", "BUY"]).unwrap();
+        assert_eq!(cli.mode(), Mode::DryRun);
+        let cli = parse(&["rm", "--show-script", "BUY", "--dry-run"]).unwrap();
+        assert_eq!(cli.mode(), Mode::ShowScript);
+        assert_eq!(parse(&["rm", "BUY"]).unwrap().mode(), Mode::Apply);
+    }
+
+    #[test]
+    fn test_aliases_and_views() {
+        let cli = parse(&["done", "someday", "REA"]).unwrap();
+        match cli.command {
+            Some(Command::Complete(targets)) => assert_eq!(targets.args, vec!["someday", "REA"]),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(parse(&["i"]).unwrap().command, Some(Command::Interactive)));
+        assert!(matches!(
+            parse(&["projects"]).unwrap().command,
+            Some(Command::Project { command: None })
+        ));
+        match parse(&["quick"]).unwrap().command {
+            Some(Command::View(args)) => assert_eq!(args, vec!["quick"]),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_add_keeps_quick_add_text() {
+        let cli = parse(&["add", "Wedding", "--heading", "Venue", "Call", "--", "2%"]).unwrap();
+        match cli.command {
+            Some(Command::Add { heading, text }) => {
+                // add_todo takes an inline --heading from the text:
+                assert_eq!(heading, None);
+                assert_eq!(text, vec!["Wedding", "--heading", "Venue", "Call", "--", "2%"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_global_flags_in_add_text() {
+        // Rejected before a backend is even opened, so nothing is added:
+        for args in [&["add", "x", "--dry-run"][..], &["add", "Buy", "milk", "--format=json"]] {
+            let args = args.iter().map(|a| a.to_string()).collect();
+            let error = run(args).unwrap_err();
+            assert_eq!(error.exit_code(), 2);
+            assert!(error.to_string().contains("must come before the todo text"));
+        }
+        assert!(check_add_text(&["x".to_string(), "--".to_string(), "--json".to_string()]).is_ok());
+        assert!(check_add_text(&["x".to_string(), "--checklist".to_string(), "a".to_string()]).is_ok());
+    }
+
+    #[test]
+    fn test_lacks_dry_run() {
+        let lacks = |args: &[&str]| parse(args).unwrap().command.unwrap().lacks_dry_run();
+        assert_eq!(lacks(&["add", "Buy milk"]), Some("add"));
+        assert_eq!(lacks(&["project", "rm", "WEB"]), Some("project rm"));
+        assert_eq!(lacks(&["project", "ls"]), None);
+        assert_eq!(lacks(&["mv", "BUY", "someday"]), None);
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        let error = run(vec!["rm".to_string()]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+        assert!(error.to_string().contains("<ID>"));
+        assert!(error.hint().unwrap().starts_with("Usage: thingy rm"));
+
+        let error = run(vec!["log".to_string(), "0".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("'0'"));
+        let error = run(vec!["--dry-run".to_string(), "rand".to_string()]).unwrap_err();
+        assert_eq!(error.to_string(), "'rand' doesn't support --dry-run");
+        let error = run(vec!["--json".to_string(), "show".to_string(), "BUY".to_string()]).unwrap_err();
+        assert_eq!(error.to_string(), "'show' doesn't support --json or --format");
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,259 @@
+use chrono::Local;
+
+use crate::backend::{self, Backend, Op};
+use crate::commands::{fetch_todos_in_lists, visible_lists};
+use crate::config;
+use crate::error::{Result, ThingyError};
+use crate::journal::Change;
+use crate::lists::resolve_list;
+use crate::matcher::{is_position, match_todo, Match};
+use crate::quickadd;
+use crate::todo::Todo;
+
+/// thingy's commands for use from Rust.  Todos are named as on the
+/// command line: by identifier, list position or name, looked up in the
+/// default list, Inbox and Today.  Changes are journaled, so `thingy
+/// undo` can undo them.
+pub struct Client {
+    backend: Box<dyn Backend>,
+}
+
+impl Client {
+    /// A client for `backend`, using the config `from_env` loaded, or the
+    /// defaults if it hasn't been called.
+    pub fn new(backend: Box<dyn Backend>) -> Self {
+        Client { backend }
+    }
+
+    /// A client using the user's config file (as `thingy` does) and the
+    /// backend `THINGY_BACKEND` names (see `backend::from_env`).
+    ///
+    /// ```
+    /// # let dir = std::env::temp_dir().join(format!("thingy-doc-{}", std::process::id()));
+    /// # std::fs::create_dir_all(&dir).unwrap();
+    /// # std::fs::write(dir.join("config.toml"), "default_list = \"Inbox\"\n").unwrap();
+    /// # for (name, file) in [("THINGY_CONFIG", "config.toml"), ("THINGY_FILE", "todos.json"),
+    /// #     ("THINGY_REGISTRY", "ids.json"), ("THINGY_JOURNAL", "journal.json")] {
+    /// #     std::env::set_var(name, dir.join(file));
+    /// # }
+    /// # std::env::set_var("THINGY_BACKEND", "file");
+    /// // With `default_list = "Inbox"` in the config file:
+    /// let client = thingy::Client::from_env()?;
+    /// client.add("inbox", "Buy milk")?;
+    /// let done = client.complete(&["1"])?;
+    /// assert_eq!(done[0].name, "Buy milk");
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # Ok::<(), thingy::ThingyError>(())
+    /// ```
+    pub fn from_env() -> Result<Self> {
+        config::init().map_err(ThingyError::Parse)?;
+        Ok(Client::new(backend::from_env()?))
+    }
+
+    pub fn backend(&self) -> &dyn Backend {
+        self.backend.as_ref()
+    }
+
+    /// Open todos in `list`, a list, area or project name or prefix.
+    pub fn list(&self, list: &str) -> Result<Vec<Todo>> {
+        let list_name = self.require_list(list)?;
+        self.backend.fetch_todos(&list_name)
+    }
+
+    /// The todos `ids` refer to, in order and without repeats.
+    pub fn find(&self, ids: &[&str]) -> Result<Vec<Todo>> {
+        let todos = fetch_todos_in_lists(self.backend(), &visible_lists())?;
+        let mut found: Vec<Todo> = Vec::new();
+        for id in ids {
+            let todo = match match_todo(&todos, id) {
+                Match::Unique(todo) => todo,
+                Match::Ambiguous(candidates) | Match::Guess(candidates) => {
+                    return Err(ThingyError::AmbiguousId {
+                        query: id.to_string(),
+                        candidates: candidates
+                            .iter()
+                            .map(|todo| format!("{} {}", todo.identifier, todo.name))
+                            .collect(),
+                    })
+                }
+                Match::None if is_position(id) => {
+                    return Err(ThingyError::not_found(format!("No todo at position {}", id.trim()), None))
+                }
+                Match::None => {
+                    return Err(ThingyError::not_found(format!("No todo found for '{}'", id), None))
+                }
+            };
+            if !found.iter().any(|t| t.id == todo.id) {
+                found.push(todo.clone());
+            }
+        }
+        Ok(found)
+    }
+
+    /// Adds a todo to `list`, with `text` in the `thingy add` syntax
+    /// (`Buy milk #errand @tomorrow`).  Returns the new todo's name.
+    pub fn add(&self, list: &str, text: &str) -> Result<String> {
+        let list_name = self.require_list(list)?;
+        let todo =
+            quickadd::parse_line(text, Local::now().date_naive()).map_err(ThingyError::Parse)?;
+        self.backend.add_todo(&list_name, &todo)
+    }
+
+    /// Completes the todos `ids` refer to, returning them as they were.
+    pub fn complete(&self, ids: &[&str]) -> Result<Vec<Todo>> {
+        self.change("complete", ids, |todo| Op::Complete {
+            id: todo.id.clone(),
+            tags: todo.tags.clone(),
+        })
+    }
+
+    /// Moves the todos `ids` refer to into `list`, returning them as they
+    /// were.
+    pub fn move_to(&self, ids: &[&str], list: &str) -> Result<Vec<Todo>> {
+        let to_list = self.require_list(list)?;
+        self.change("mv", ids, |todo| Op::Move {
+            id: todo.id.clone(),
+            to_list: to_list.clone(),
+        })
+    }
+
+    /// Adds `tags` to the todos `ids` refer to, returning them as they
+    /// were.
+    pub fn tag(&self, ids: &[&str], tags: &[&str]) -> Result<Vec<Todo>> {
+        let add: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+        self.change("tag", ids, |todo| Op::EditTags {
+            id: todo.id.clone(),
+            add: add.clone(),
+            remove: Vec::new(),
+        })
+    }
+
+    pub fn notes(&self, id: &str) -> Result<String> {
+        let todo = self.find(&[id])?.remove(0);
+        self.backend.fetch_notes(&todo.id)
+    }
+
+    /// Todos completed within the last `days` days, most recent first.
+    pub fn logbook(&self, days: u32) -> Result<Vec<Todo>> {
+        self.backend.fetch_logbook(days)
+    }
+
+    fn require_list(&self, name: &str) -> Result<String> {
+        resolve_list(self.backend(), name, true)?
+            .ok_or_else(|| ThingyError::not_found(format!("Unknown list '{}'", name), None))
+    }
+
+    fn change(&self, action: &str, ids: &[&str], op: impl Fn(&Todo) -> Op) -> Result<Vec<Todo>> {
+        let todos = self.find(ids)?;
+        let ops: Vec<Op> = todos.iter().map(op).collect();
+        let results = apply_journaled(self.backend(), action, &todos, &ops);
+
+        let total = todos.len();
+        let mut changed = Vec::new();
+        let mut errors = Vec::new();
+        for (todo, result) in todos.into_iter().zip(results) {
+            match result {
+                Ok(_) => changed.push(todo),
+                Err(error) => errors.push(error),
+            }
+        }
+        batch_result(errors, total)?;
+        Ok(changed)
+    }
+}
+
+/// Applies `ops`, one for each of `todos`, as one batch, journaling the
+/// changes that worked as `action`.  Changes handed off by URL count as
+/// worked; undoing one that Things3 dropped just restores the todo as it
+/// was.
+pub(crate) fn apply_journaled(
+    backend: &dyn Backend,
+    action: &str,
+    todos: &[Todo],
+    ops: &[Op],
+) -> Vec<Result<String>> {
+    let results = backend.apply(ops);
+    let changes = todos
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_ok())
+        .map(|(todo, _)| Change::of(todo))
+        .collect();
+    backend.journal().record(action, changes);
+    results
+}
+
+/// The outcome of a batch of `total` changes that hit `errors`: a lone
+/// change's own error, or `ThingyError::Failed` for a batch.
+pub(crate) fn batch_result(mut errors: Vec<ThingyError>, total: usize) -> Result<()> {
+    match errors.len() {
+        0 => Ok(()),
+        _ if total == 1 => Err(errors.remove(0)),
+        failed => Err(ThingyError::Failed {
+            failed,
+            total,
+            first: Box::new(errors.remove(0)),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_backend::FileBackend;
+
+    fn client() -> Client {
+        let client = Client::new(Box::new(FileBackend::in_memory()));
+        for text in ["Buy milk #errand", "Call mom", "Fix bike"] {
+            client.add("today", text).unwrap();
+        }
+        client
+    }
+
+    fn names(todos: &[Todo]) -> Vec<&str> {
+        todos.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_and_add() {
+        let client = client();
+        let todos = client.list("tod").unwrap();
+        assert_eq!(names(&todos), vec!["Buy milk", "Call mom", "Fix bike"]);
+        assert_eq!(todos[0].tags, vec!["errand"]);
+        assert!(matches!(client.list("nowhere"), Err(ThingyError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_complete_move_and_tag() {
+        let client = client();
+        let completed = client.complete(&["BUY", "fix"]).unwrap();
+        assert_eq!(names(&completed), vec!["Buy milk", "Fix bike"]);
+        assert_eq!(names(&client.list("today").unwrap()), vec!["Call mom"]);
+
+        client.tag(&["CAL"], &["phone"]).unwrap();
+        let moved = client.move_to(&["CAL"], "someday").unwrap();
+        assert_eq!(moved[0].tags, vec!["phone"]);
+        let someday = client.list("someday").unwrap();
+        assert_eq!(names(&someday), vec!["Call mom"]);
+        assert_eq!(someday[0].tags, vec!["phone"]);
+    }
+
+    #[test]
+    fn test_find_errors() {
+        let client = client();
+        client.add("inbox", "Buy bread").unwrap();
+        assert!(matches!(client.find(&["bu"]), Err(ThingyError::AmbiguousId { .. })));
+        assert!(matches!(client.complete(&["ZZZ"]), Err(ThingyError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_notes_and_logbook() {
+        let client = client();
+        client.add("inbox", "Read book -- chapter 3").unwrap();
+        assert_eq!(client.notes("REA").unwrap(), "chapter 3");
+
+        client.complete(&["CAL"]).unwrap();
+        client.backend().log_completed().unwrap();
+        assert_eq!(names(&client.logbook(1).unwrap()), vec!["Call mom"]);
+    }
+}
@@ -1,514 +1,880 @@
-use crate::applescript::{run_applescript, parse_list_name, FILTER_COMPLETED};
+use crate::backend::{Backend, Op};
+use crate::client::{apply_journaled, batch_result};
+use crate::lists::{assign_list_identifiers, find_list, resolve_list, ListInfo, ListKind, BUILT_IN_LISTS};
+use crate::output::{self, Format};
+use crate::config;
+use crate::error::{Result, ThingyError};
+use crate::identifiers;
+use crate::journal::{self, Journal};
+use crate::matcher::{is_position, match_todo, Match};
+use crate::query::{self, Expr, SortKey};
+use crate::quickadd;
+use crate::tags::tag_tree_lines;
 use crate::todo::Todo;
+use chrono::Local;
 use rand::Rng;
+use std::io::{self, IsTerminal};
 
-pub fn show_help() {
-    eprintln!("Usage: thingy [command] [args]");
-    eprintln!();
-    eprintln!("Commands:");
-    eprintln!("  (no args)             Show today's todos");
-    eprintln!("  help, -h              Show this help message");
-    eprintln!("  add [list] <text>     Add a new todo (defaults to today)");
-    eprintln!("  inbox                 Show current inbox todos");
-    eprintln!("  today                 Show current today todos");
-    eprintln!("  inprog                Show in-progress todos from today");
-    eprintln!("  completed             Show completed todos from today");
-    eprintln!("  finished              Alias for completed");
-    eprintln!("  count                 Show count of non-completed today todos");
-    eprintln!("  total                 Alias for count");
-    eprintln!("  rm [list] <id>        Remove todo by identifier (defaults to today)");
-    eprintln!("  complete [list] <id...> Mark todo(s) complete by identifier");
-    eprintln!("  done [list] <id...>     Alias for complete");
-    eprintln!("  finish [list] <id...>   Alias for complete");
-    eprintln!("  mv <id>               Move todo from inbox to today by identifier");
-    eprintln!("  mv <from> <id> [to]   Move todo between lists (defaults to today)");
-    eprintln!("  workon [list] <id>    Tag todo as in-progress by identifier");
-    eprintln!("  rand                  Pick a random todo from today and mark it in-progress");
-    eprintln!("  next [list] <id>      Tag todo as on-deck by identifier");
-    eprintln!("  next                  Show the on-deck todo");
-    eprintln!("  ondeck                Alias for next");
-    eprintln!("  show [list] <id>      Show notes for a todo by identifier");
-    eprintln!("  view [list] <id>      Alias for show");
-    eprintln!("  log [days]            Show logbook entries (defaults to 1 day)");
-    eprintln!("  defer <id>            Defer todo from today to tomorrow");
-    eprintln!("  untagged              Show all untagged todos");
-    eprintln!("  soonest               Show the todo with the shortest time tag");
-    eprintln!("  interactive           Interactive mode with keyboard navigation");
-    eprintln!("  i                     Alias for interactive");
-}
-
-pub fn add_todo(args: &[String]) {
-    if args.is_empty() {
-        eprintln!("Error: 'add' command requires todo text");
-        eprintln!("Usage: thingy add [list] <todo text>");
-        std::process::exit(1);
+/// The list commands use when they aren't given one.
+fn default_list() -> String {
+    config::get().default_list.clone()
+}
+
+/// Resolves `name` to a list; it's an error if there's no such list (or
+/// the prefix is ambiguous).
+fn require_list(backend: &dyn Backend, name: &str) -> Result<String> {
+    resolve_list(backend, name, true)?.ok_or_else(|| {
+        ThingyError::not_found(
+            format!("Unknown list '{}'", name),
+            "Use 'thingy lists' to see available lists".to_string(),
+        )
+    })
+}
+
+/// If `args` starts with a list name (matched exactly, since the rest of
+/// `args` is free text or identifiers), returns it and the remaining args.
+fn split_leading_list<'a>(
+    backend: &dyn Backend,
+    args: &'a [String],
+) -> Result<(Option<String>, &'a [String])> {
+    if args.len() >= 2 {
+        if let Some(list_name) = resolve_list(backend, &args[0], false)? {
+            return Ok((Some(list_name), &args[1..]));
+        }
     }
+    Ok((None, args))
+}
 
-    let (list_name, text_args) = match args[0].to_lowercase().as_str() {
-        "inbox" => {
-            if args.len() < 2 {
-                eprintln!("Error: 'add' command requires todo text");
-                eprintln!("Usage: thingy add [list] <todo text>");
-                std::process::exit(1);
-            }
-            ("Inbox", &args[1..])
+/// The lists identifiers are looked up in when no list is given: the
+/// default list, then Inbox and Today.
+pub(crate) fn visible_lists() -> Vec<String> {
+    let mut lists = vec![default_list()];
+    for name in ["Inbox", "Today"] {
+        if !lists.iter().any(|l| l == name) {
+            lists.push(name.to_string());
         }
-        "today" => {
-            if args.len() < 2 {
-                eprintln!("Error: 'add' command requires todo text");
-                eprintln!("Usage: thingy add [list] <todo text>");
-                std::process::exit(1);
+    }
+    lists
+}
+
+/// `list`, or the visible lists if there isn't one.
+fn lookup_lists(list: Option<String>) -> Vec<String> {
+    list.map(|l| vec![l]).unwrap_or_else(visible_lists)
+}
+
+/// The todos in `lists`, with identifiers unique across all of them.
+/// Numbers still refer to positions in the first list: todos from the
+/// others are unnumbered.
+pub(crate) fn fetch_todos_in_lists(backend: &dyn Backend, lists: &[String]) -> Result<Vec<Todo>> {
+    let mut todos: Vec<Todo> = Vec::new();
+    for (n, list_name) in lists.iter().enumerate() {
+        for mut todo in backend.fetch_todos(list_name)? {
+            if !todos.iter().any(|t| t.id == todo.id) {
+                if n > 0 {
+                    todo.index = 0;
+                }
+                todos.push(todo);
             }
-            ("Today", &args[1..])
         }
-        _ => ("Today", args)
+    }
+    if lists.len() > 1 {
+        identifiers::assign_identifiers(&mut todos, &backend.registry());
+    }
+    Ok(todos)
+}
+
+fn ls_hint(lists: &[String]) -> String {
+    let commands: Vec<String> = lists
+        .iter()
+        .map(|l| format!("'thingy ls {}'", l.to_lowercase()))
+        .collect();
+    format!("Use {} to see available todos", commands.join(" or "))
+}
+
+/// Adds a todo from `args`, `[list] <text>` in the quick-add syntax.  The
+/// heading may be given as `heading` or in `args`, after the project.
+pub fn add_todo(backend: &dyn Backend, args: &[String], heading: Option<String>) -> Result<()> {
+    let mut args = args.to_vec();
+    let heading = match heading {
+        Some(heading) => Some(heading),
+        None => take_option(&mut args, "--heading")?,
     };
 
-    let todo_text = text_args.join(" ");
-    let escaped_text = todo_text.replace("\\", "\\\\").replace("\"", "\\\"");
-
-    let script = format!(
-        r#"
-tell application "Things3"
-    set newTodo to make new to do with properties {{name:"{}"}}
-    move newTodo to list "{}"
-    return name of newTodo
-end tell
-"#,
-        escaped_text, list_name
-    );
-
-    match run_applescript(&script) {
-        Ok(result) => {
-            println!("Added to {}: {}", list_name, result.trim());
-        }
-        Err(error) => {
-            eprintln!("Error adding todo: {}", error);
-            std::process::exit(1);
+    if args.is_empty() {
+        return Err(ThingyError::usage(
+            "'add' command requires todo text",
+            "thingy add [list] <todo text> [#tag] [@when] [!deadline] [-- notes]\n       \
+             thingy add <project> --heading <heading> <todo text>"
+                .to_string(),
+        ));
+    }
+
+    let (list_name, text_args) = split_leading_list(backend, &args)?;
+    let list_name = list_name.unwrap_or_else(default_list);
+    let mut todo =
+        quickadd::parse(text_args, Local::now().date_naive()).map_err(ThingyError::Parse)?;
+
+    if heading.is_some() {
+        let is_project = backend
+            .fetch_lists()
+            .map(|lists| {
+                lists
+                    .iter()
+                    .any(|l| l.kind == ListKind::Project && l.name == list_name)
+            })
+            .unwrap_or(false);
+        if !is_project {
+            return Err(ThingyError::usage(
+                format!("--heading needs a project to add to, not '{}'", list_name),
+                None,
+            ));
         }
+        todo.heading = heading;
+    }
+
+    let name = backend.add_todo(&list_name, &todo)?;
+    match &todo.heading {
+        Some(heading) => println!("Added to {} / {}: {}", list_name, heading, name),
+        None => println!("Added to {}: {}", list_name, name),
     }
+    Ok(())
 }
 
-fn resolve_id(todos: &[Todo], id_str: &str, list_name: &str) -> usize {
+fn find_todo<'a>(todos: &'a [Todo], id_str: &str) -> Option<&'a Todo> {
     if let Ok(n) = id_str.parse::<usize>() {
-        if n > 0 {
-            return n;
+        if let Some(todo) = todos.iter().find(|t| t.index == n && n > 0) {
+            return Some(todo);
         }
     }
 
     let id_upper = id_str.to_uppercase();
-    for todo in todos {
-        if todo.identifier == id_upper {
-            return todo.index;
+    todos.iter().find(|t| t.identifier == id_upper)
+}
+
+/// Finds the todo `id_str` refers to (see `matcher::match_todo`), asking
+/// which one is meant if several match and we're in a terminal.
+fn resolve_id<'a>(todos: &'a [Todo], id_str: &str, lists: &[String]) -> Result<&'a Todo> {
+    match match_todo(todos, id_str) {
+        Match::Unique(todo) => Ok(todo),
+        Match::Ambiguous(candidates) | Match::Guess(candidates) => {
+            choose_candidate(&candidates, id_str)
         }
+        Match::None if is_position(id_str) => Err(ThingyError::not_found(
+            format!("No todo at position {} in {}", id_str.trim(), lists[0]),
+            ls_hint(lists),
+        )),
+        Match::None => Err(ThingyError::not_found(
+            format!("No todo found with identifier or number '{}'", id_str),
+            ls_hint(lists),
+        )),
     }
+}
 
-    eprintln!("Error: No todo found with identifier or number '{}'", id_str);
-    eprintln!("Use 'thingy {}' to see available todos", list_name.to_lowercase());
-    std::process::exit(1);
+fn choose_candidate<'a>(candidates: &[&'a Todo], id_str: &str) -> Result<&'a Todo> {
+    let ambiguous = ThingyError::AmbiguousId {
+        query: id_str.to_string(),
+        candidates: candidates
+            .iter()
+            .map(|todo| format!("{} {}", todo.identifier, todo_display_text(todo)))
+            .collect(),
+    };
+    if !(io::stdin().is_terminal() && io::stderr().is_terminal()) {
+        return Err(ambiguous);
+    }
+
+    eprintln!("{}", ambiguous);
+    eprint!("Which one? [1-{}] ", candidates.len());
+    let mut answer = String::new();
+    let _ = io::stdin().read_line(&mut answer);
+    match answer.trim().parse::<usize>() {
+        Ok(n) if (1..=candidates.len()).contains(&n) => Ok(candidates[n - 1]),
+        _ => Err(ThingyError::Other("No todo chosen".to_string())),
+    }
 }
 
-fn parse_list_and_identifier(args: &[String], todos: &[Todo]) -> (&'static str, usize) {
-    if args.is_empty() {
-        eprintln!("Error: Missing todo identifier or number");
-        std::process::exit(1);
+/// Parses `[list] <id>` (looking in the visible lists if no list is
+/// given) and returns the list and the todo the identifier names.
+fn parse_list_and_identifier(backend: &dyn Backend, args: &[String]) -> Result<(String, Todo)> {
+    let (lists, id_str) = match args {
+        [] => return Err(missing_identifier()),
+        [id_str] => (visible_lists(), id_str),
+        [list, id_str, ..] => (vec![require_list(backend, list)?], id_str),
+    };
+
+    let todos = fetch_todos_in_lists(backend, &lists)?;
+    let todo = resolve_id(&todos, id_str, &lists)?.clone();
+    Ok((todo.list.clone(), todo))
+}
+
+fn missing_identifier() -> ThingyError {
+    ThingyError::usage("Missing todo identifier or number", None)
+}
+
+/// Parses `[list] <id...>` and returns the todos named, in order and
+/// without repeats.
+fn parse_list_and_identifiers(backend: &dyn Backend, args: &[String]) -> Result<Vec<Todo>> {
+    let (list_name, id_args) = split_leading_list(backend, args)?;
+    if id_args.is_empty() {
+        return Err(missing_identifier());
     }
+    let lists = lookup_lists(list_name);
+    let todos = fetch_todos_in_lists(backend, &lists)?;
+    Ok(select_todos(&todos, id_args, &lists)?.into_iter().cloned().collect())
+}
 
-    let (list_name, id_str) = if args.len() == 1 {
-        ("Today", &args[0])
-    } else {
-        match args[0].to_lowercase().as_str() {
-            "inbox" => ("Inbox", &args[1]),
-            "today" => ("Today", &args[1]),
-            _ => {
-                eprintln!("Error: Unknown list '{}'", args[0]);
-                eprintln!("Valid lists: inbox, today");
-                std::process::exit(1);
-            }
+fn select_todos<'a>(
+    todos: &'a [Todo],
+    id_args: &[String],
+    lists: &[String],
+) -> Result<Vec<&'a Todo>> {
+    let mut selected: Vec<&Todo> = Vec::new();
+    for id_str in id_args {
+        let todo = resolve_id(todos, id_str, lists)?;
+        if !selected.iter().any(|t| t.id == todo.id) {
+            selected.push(todo);
         }
-    };
+    }
+    Ok(selected)
+}
 
-    (list_name, resolve_id(todos, id_str, list_name))
-}
-
-fn fetch_todos_for_list(list_name: &str) -> Vec<Todo> {
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "{}"
-    {}
-    set output to ""
-    set oldDelimiters to AppleScript's text item delimiters
-    repeat with todo in listTodos
-        set todoName to name of todo
-        set todoTags to tag names of todo
-        if (count of todoTags) > 0 then
-            set AppleScript's text item delimiters to ", "
-            set tagString to todoTags as string
-            set AppleScript's text item delimiters to oldDelimiters
-            set output to output & todoName & "|" & tagString & "\n"
-        else
-            set output to output & todoName & "|\n"
-        end if
-    end repeat
-    set AppleScript's text item delimiters to oldDelimiters
-    return output
-end tell
-"#,
-        list_name, FILTER_COMPLETED
-    );
-
-    match run_applescript(&script) {
-        Ok(result) => {
-            let mut todos = Vec::new();
-            for (idx, line) in result.trim().lines().enumerate() {
-                let parts: Vec<&str> = line.split('|').collect();
-                if parts.len() >= 1 {
-                    let name = parts[0].to_string();
-                    let tags = if parts.len() >= 2 && !parts[1].is_empty() {
-                        parts[1].to_string()
-                    } else {
-                        String::new()
-                    };
-                    todos.push(Todo {
-                        name,
-                        tags,
-                        is_completed: false,
-                        index: idx + 1,
-                        identifier: String::new(),
-                    });
-                }
-            }
-            crate::identifiers::assign_identifiers(&mut todos);
-            todos
+/// Whether commands that change todos make the changes or only say what
+/// they would change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Apply,
+    DryRun,
+    /// A dry run that also prints the script the backend would run.
+    ShowScript,
+}
+
+/// What `op` would do to the todo described by `label`.
+fn describe_op(op: &Op, label: &str) -> String {
+    match op {
+        Op::Complete { .. } => format!("complete {}", label),
+        Op::Delete { .. } => format!("remove {}", label),
+        Op::Move { to_list, .. } => format!("move {} to {}", label, to_list),
+        Op::Defer { .. } => format!("defer {} to tomorrow", label),
+        Op::EditTags { add, remove, .. } => {
+            let changes: Vec<String> = add
+                .iter()
+                .map(|tag| format!("+{}", tag))
+                .chain(remove.iter().map(|tag| format!("-{}", tag)))
+                .collect();
+            format!("tag {} {}", label, changes.join(" "))
         }
-        Err(error) => {
-            eprintln!("Error fetching todos: {}", error);
-            std::process::exit(1);
+        Op::Restore { before, .. } => format!(
+            "restore {} to {}{}",
+            label,
+            before.list,
+            if before.completed { " (completed)" } else { "" }
+        ),
+    }
+}
+
+/// Prints what `ops` would do to the todos described by `labels`, and
+/// with `Mode::ShowScript` the script that would do it.
+fn show_plan(backend: &dyn Backend, mode: Mode, labels: &[String], ops: &[Op]) {
+    for (label, op) in labels.iter().zip(ops) {
+        println!("Would {}", describe_op(op, label));
+    }
+    if mode == Mode::ShowScript {
+        match backend.script(ops) {
+            Some(script) => print!("\n{}", script),
+            None => eprintln!("Note: this backend doesn't run scripts"),
         }
     }
 }
 
-fn fetch_completed_todos() -> Vec<Todo> {
-    let script = r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    set allTodos to to dos of listToQuery
-    set output to ""
-    set oldDelimiters to AppleScript's text item delimiters
-    repeat with todo in allTodos
-        if status of todo is completed then
-            set todoName to name of todo
-            set todoTags to tag names of todo
-            if (count of todoTags) > 0 then
-                set AppleScript's text item delimiters to ", "
-                set tagString to todoTags as string
-                set AppleScript's text item delimiters to oldDelimiters
-                set output to output & todoName & "|" & tagString & "\n"
-            else
-                set output to output & todoName & "|\n"
-            end if
-        end if
-    end repeat
-    set AppleScript's text item delimiters to oldDelimiters
-    return output
-end tell
-"#;
-
-    match run_applescript(script) {
-        Ok(result) => {
-            let mut todos = Vec::new();
-            for (idx, line) in result.trim().lines().enumerate() {
-                let parts: Vec<&str> = line.split('|').collect();
-                if parts.len() >= 1 {
-                    let name = parts[0].to_string();
-                    let tags = if parts.len() >= 2 && !parts[1].is_empty() {
-                        parts[1].to_string()
-                    } else {
-                        String::new()
-                    };
-                    todos.push(Todo {
-                        name,
-                        tags,
-                        is_completed: true,
-                        index: idx + 1,
-                        identifier: String::new(),
-                    });
+/// Applies `op` to each of `todos` as one batch, printing `describe` of
+/// each todo changed (given the todo and its name) and an error for each
+/// failure.  The changes are journaled as `action` for `thingy undo`.
+/// A dry run only prints the plan.
+fn apply_to_todos(
+    backend: &dyn Backend,
+    mode: Mode,
+    action: &str,
+    todos: &[Todo],
+    op: impl Fn(&Todo) -> Op,
+    describe: impl Fn(&Todo, &str) -> String,
+) -> Result<()> {
+    let ops: Vec<Op> = todos.iter().map(op).collect();
+    if mode != Mode::Apply {
+        let labels: Vec<String> = todos
+            .iter()
+            .map(|todo| format!("{} {}", todo.identifier, todo.name))
+            .collect();
+        show_plan(backend, mode, &labels, &ops);
+        return Ok(());
+    }
+    let results = apply_journaled(backend, action, todos, &ops);
+
+    let mut errors = Vec::new();
+    let mut unconfirmed = false;
+    for (todo, result) in todos.iter().zip(results) {
+        match result {
+            Ok(name) if name.is_empty() => {
+                unconfirmed = true;
+                println!("{}", describe(todo, &todo.name));
+            }
+            Ok(name) => println!("{}", describe(todo, &name)),
+            Err(error) => {
+                if todos.len() > 1 {
+                    eprintln!("Error: {} {}: {}", todo.identifier, todo.name, error);
                 }
+                errors.push(error);
             }
-            crate::identifiers::assign_identifiers(&mut todos);
-            todos
-        }
-        Err(error) => {
-            eprintln!("Error fetching completed todos: {}", error);
-            std::process::exit(1);
         }
     }
+    if unconfirmed {
+        eprintln!("Note: sent to Things3, which doesn't confirm changes made by URL");
+    }
+    batch_result(errors, todos.len())
 }
 
-pub fn remove_todo(args: &[String]) {
-    let list_name = if args.len() >= 2 {
-        match args[0].to_lowercase().as_str() {
-            "inbox" => "Inbox",
-            "today" => "Today",
-            _ => "Today",
-        }
+pub fn remove_todo(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    let todos = parse_list_and_identifiers(backend, args)?;
+    apply_to_todos(
+        backend,
+        mode,
+        "rm",
+        &todos,
+        |todo| Op::Delete { id: todo.id.clone() },
+        |todo, name| format!("Removed from {}: {}", todo.list, name),
+    )
+}
+
+pub fn complete_todo(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    let todos = parse_list_and_identifiers(backend, args)?;
+    apply_to_todos(
+        backend,
+        mode,
+        "complete",
+        &todos,
+        |todo| Op::Complete {
+            id: todo.id.clone(),
+            tags: todo.tags.clone(),
+        },
+        |_, name| format!("Completed: {}", name),
+    )
+}
+
+/// Where `mv` moves todos when not given a list to move to.
+const MOVE_DESTINATION: &str = "Today";
+
+/// Moves todos, `[from] <id...> [to]`: `from` and `to` are taken as lists
+/// when they name one in full (so identifiers aren't mistaken for list
+/// prefixes), else as identifiers.  Without `from`, identifiers are looked
+/// up across the visible lists; without `to`, todos go to
+/// `MOVE_DESTINATION`.
+pub fn move_todo(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    if args.is_empty() {
+        return Err(ThingyError::usage(
+            "'mv' command requires todo identifier or number",
+            "thingy mv <id...>\n       thingy mv [from] <id...> [to]".to_string(),
+        ));
+    }
+    let (from_list, rest) = match args {
+        [first, rest @ ..] if !rest.is_empty() => match resolve_list(backend, first, false)? {
+            Some(from_list) => (Some(from_list), rest),
+            None => (None, args),
+        },
+        _ => (None, args),
+    };
+    let (id_args, to_list) = match rest {
+        [ids @ .., to] if !ids.is_empty() => match resolve_list(backend, to, false)? {
+            Some(to_list) => (ids, to_list),
+            None => (rest, MOVE_DESTINATION.to_string()),
+        },
+        _ => (rest, MOVE_DESTINATION.to_string()),
+    };
+
+    let lists = lookup_lists(from_list);
+    let todos = fetch_todos_in_lists(backend, &lists)?;
+    let selected: Vec<Todo> = select_todos(&todos, id_args, &lists)?.into_iter().cloned().collect();
+    apply_to_todos(
+        backend,
+        mode,
+        "mv",
+        &selected,
+        |todo| Op::Move {
+            id: todo.id.clone(),
+            to_list: to_list.clone(),
+        },
+        |todo, name| format!("Moved from {} to {}: {}", todo.list, to_list, name),
+    )
+}
+
+pub fn show_inbox(backend: &dyn Backend, format: Format) -> Result<()> {
+    show_list(backend, "Inbox", format)
+}
+
+pub fn show_today(backend: &dyn Backend, format: Format) -> Result<()> {
+    show_list(backend, "Today", format)
+}
+
+pub fn show_default_list(backend: &dyn Backend, format: Format) -> Result<()> {
+    show_list(backend, &default_list(), format)
+}
+
+/// Removes `<name> <value>` or `<name>=<value>` from `args`, returning the
+/// value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let prefix = format!("{}=", name);
+    let Some(pos) = args.iter().position(|a| a == name || a.starts_with(&prefix)) else {
+        return Ok(None);
+    };
+    if let Some(value) = args[pos].strip_prefix(&prefix) {
+        let value = value.to_string();
+        args.remove(pos);
+        return Ok(Some(value));
+    }
+    if pos + 1 >= args.len() {
+        return Err(ThingyError::usage(format!("{} requires a value", name), None));
+    }
+    let value = args.remove(pos + 1);
+    args.remove(pos);
+    Ok(Some(value))
+}
+
+pub fn list_todos(
+    backend: &dyn Backend,
+    list: Option<&str>,
+    filter: Option<&str>,
+    sort: Option<&str>,
+    format: Format,
+) -> Result<()> {
+    let filter = filter
+        .map(|q| query::parse(q, Local::now().date_naive()))
+        .transpose()
+        .map_err(ThingyError::Parse)?;
+    let sort_keys = sort.map(query::parse_sort).transpose().map_err(ThingyError::Parse)?;
+
+    let list_name = match list {
+        Some(name) => require_list(backend, name)?,
+        None => default_list(),
+    };
+    if filter.is_none() && sort_keys.is_none() {
+        return show_list(backend, &list_name, format);
+    }
+    show_filtered(backend, &list_name, filter.as_ref(), sort_keys.as_deref(), format)
+}
+
+fn show_filtered(
+    backend: &dyn Backend,
+    list_name: &str,
+    filter: Option<&Expr>,
+    sort_keys: Option<&[SortKey]>,
+    format: Format,
+) -> Result<()> {
+    let todos = backend.fetch_todos(list_name)?;
+    let mut selected: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| filter.is_none_or(|f| f.matches(t)))
+        .collect();
+    if let Some(keys) = sort_keys {
+        query::sort_todos(&mut selected, keys);
+    }
+
+    if print_structured(&selected, format) {
+        return Ok(());
+    }
+
+    if selected.is_empty() {
+        println!("No matching todos in {}", list_name);
     } else {
-        "Today"
+        println!("{} todos:", list_name);
+        for todo in selected {
+            print_todo_line(todo);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the view called `name` from the config file, returning false if
+/// there's no such view.
+pub fn run_view(backend: &dyn Backend, name: &str, format: Format) -> Result<bool> {
+    let Some(view) = config::get().views.get(name) else {
+        return Ok(false);
     };
 
-    let todos = fetch_todos_for_list(list_name);
-    let (list_name, todo_num) = parse_list_and_identifier(args, &todos);
-
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "{}"
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoToDelete to item {} of listTodos
-    set todoName to name of todoToDelete
-    delete todoToDelete
-    return todoName
-end tell
-"#,
-        list_name, FILTER_COMPLETED, todo_num, todo_num, todo_num
-    );
-
-    match run_applescript(&script) {
-        Ok(todo_name) => {
-            println!("Removed from {}: {}", list_name, todo_name.trim());
+    let (filter, sort_keys) = match (view.filter(Local::now().date_naive()), view.sort_keys()) {
+        (Ok(filter), Ok(sort_keys)) => (filter, sort_keys),
+        (Err(error), _) | (_, Err(error)) => {
+            return Err(ThingyError::Parse(format!("Error in view '{}': {}", name, error)));
         }
-        Err(error) => {
-            eprintln!("Error removing todo: {}", error);
-            std::process::exit(1);
+    };
+    let list_name = match &view.list {
+        Some(list) => require_list(backend, list)?,
+        None => default_list(),
+    };
+    show_filtered(backend, &list_name, filter.as_ref(), sort_keys.as_deref(), format)?;
+    Ok(true)
+}
+
+pub fn show_views() {
+    let config = config::get();
+    if config.views.is_empty() {
+        println!("No views defined in {}", config::config_path().display());
+        return;
+    }
+    println!("Views:");
+    for (name, view) in &config.views {
+        match &view.description {
+            Some(description) => println!("  {:<20}  {}", name, description),
+            None => println!("  {}", name),
         }
     }
 }
 
-pub fn complete_todo(args: &[String]) {
-    if args.is_empty() {
-        eprintln!("Error: 'complete' command requires todo identifier");
-        eprintln!("Usage: thingy complete [list] <id...>");
-        std::process::exit(1);
+/// Undoes the last `count` journaled changes, most recent first.
+pub fn undo_command(backend: &dyn Backend, count: usize, mode: Mode) -> Result<()> {
+    let journal = backend.journal();
+    let entries = load_journal(&journal)?;
+    if entries.is_empty() {
+        println!("Nothing to undo");
+        return Ok(());
     }
+    for entry in entries.iter().rev().take(count) {
+        let ops: Vec<Op> = entry
+            .changes
+            .iter()
+            .map(|change| Op::Restore {
+                id: change.id.clone(),
+                before: change.before.clone(),
+            })
+            .collect();
+        if mode != Mode::Apply {
+            let labels: Vec<String> = entry.changes.iter().map(|c| c.name.clone()).collect();
+            show_plan(backend, mode, &labels, &ops);
+            continue;
+        }
 
-    let (list_name, id_args) = match args[0].to_lowercase().as_str() {
-        "inbox" => {
-            if args.len() < 2 {
-                eprintln!("Error: Missing todo identifier after list name");
-                std::process::exit(1);
+        let mut errors = Vec::new();
+        for (change, result) in entry.changes.iter().zip(backend.apply(&ops)) {
+            if let Err(error) = result {
+                eprintln!("Error: couldn't restore {}: {}", change.name, error);
+                errors.push(error);
             }
-            ("Inbox", &args[1..])
         }
-        "today" => {
-            if args.len() < 2 {
-                eprintln!("Error: Missing todo identifier after list name");
-                std::process::exit(1);
-            }
-            ("Today", &args[1..])
+        if !errors.is_empty() {
+            return Err(ThingyError::Failed {
+                failed: errors.len(),
+                total: ops.len(),
+                first: Box::new(errors.remove(0)),
+            });
         }
-        _ => ("Today", args)
-    };
+        journal.pop().map_err(ThingyError::Other)?;
+        println!("Undid {}", entry.summary());
+    }
+    Ok(())
+}
 
-    let todos = fetch_todos_for_list(list_name);
-    let mut todo_nums: Vec<usize> = Vec::new();
+pub fn show_history(backend: &dyn Backend, format: Format) -> Result<()> {
+    let entries = load_journal(&backend.journal())?;
+    if format != Format::Text {
+        let recent: Vec<&journal::Entry> = entries.iter().rev().collect();
+        print!("{}", output::render_history(&recent, format));
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("No history");
+        return Ok(());
+    }
+    for (n, entry) in entries.iter().rev().enumerate() {
+        println!(
+            "{:>3}  {}  {}",
+            n + 1,
+            entry.time.format("%Y-%m-%d %H:%M"),
+            entry.summary()
+        );
+    }
+    Ok(())
+}
 
-    for id_str in id_args {
-        if let Ok(n) = id_str.parse::<usize>() {
-            if n > 0 {
-                todo_nums.push(n);
-                continue;
-            }
+fn load_journal(journal: &Journal) -> Result<Vec<journal::Entry>> {
+    journal.entries().map_err(ThingyError::Other)
+}
+
+pub fn show_lists(backend: &dyn Backend, format: Format) -> Result<()> {
+    if format != Format::Text {
+        let mut lists: Vec<ListInfo> = BUILT_IN_LISTS
+            .iter()
+            .map(|name| ListInfo {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        lists.extend(fetch_lists_of_kind(backend, ListKind::Area)?);
+        lists.extend(fetch_lists_of_kind(backend, ListKind::Project)?);
+        print!("{}", output::render_lists(&lists, format));
+        return Ok(());
+    }
+    let user_lists = backend.fetch_lists()?;
+
+    println!("Lists:");
+    for name in BUILT_IN_LISTS {
+        println!("  {}", name);
+    }
+
+    let areas: Vec<&ListInfo> = user_lists.iter().filter(|l| l.kind == ListKind::Area).collect();
+    if !areas.is_empty() {
+        println!("Areas:");
+        for area in areas {
+            println!("  {}", area.name);
         }
+    }
 
-        let id_upper = id_str.to_uppercase();
-        let mut found = false;
-        for todo in &todos {
-            if todo.identifier == id_upper {
-                todo_nums.push(todo.index);
-                found = true;
-                break;
+    let projects: Vec<&ListInfo> = user_lists.iter().filter(|l| l.kind == ListKind::Project).collect();
+    if !projects.is_empty() {
+        println!("Projects:");
+        for project in projects {
+            match &project.area {
+                Some(area) => println!("  {} ({})", project.name, area),
+                None => println!("  {}", project.name),
             }
         }
+    }
+    Ok(())
+}
 
-        if !found {
-            eprintln!("Error: No todo found with identifier or number '{}'", id_str);
-            eprintln!("Use 'thingy {}' to see available todos", list_name.to_lowercase());
-            std::process::exit(1);
-        }
+/// The user's areas or open projects, with identifiers assigned.
+fn fetch_lists_of_kind(backend: &dyn Backend, kind: ListKind) -> Result<Vec<ListInfo>> {
+    let mut lists: Vec<ListInfo> =
+        backend.fetch_lists()?.into_iter().filter(|l| l.kind == kind).collect();
+    assign_list_identifiers(&mut lists);
+    Ok(lists)
+}
+
+fn resolve_list_info(lists: &[ListInfo], id_str: &str, what: &str) -> Result<ListInfo> {
+    find_list(lists, id_str).cloned().ok_or_else(|| {
+        ThingyError::not_found(
+            format!("No {} found with identifier, number or name '{}'", what, id_str),
+            format!("Use 'thingy {} ls' to see available {}s", what, what),
+        )
+    })
+}
+
+fn print_list_line(list: &ListInfo) {
+    match &list.area {
+        Some(area) => println!(" {} {} ({})", list.identifier, list.name, area),
+        None => println!(" {} {}", list.identifier, list.name),
     }
+}
 
-    if todo_nums.is_empty() {
-        eprintln!("Error: No valid todo identifiers provided");
-        std::process::exit(1);
-    }
-
-    todo_nums.sort_by(|a, b| b.cmp(a));
-
-    for todo_num in todo_nums {
-        complete_single_todo(list_name, todo_num);
-    }
-}
-
-fn complete_single_todo(list_name: &str, todo_num: usize) {
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "{}"
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoToComplete to item {} of listTodos
-    set todoName to name of todoToComplete
-
-    set currentTags to tag names of todoToComplete
-    if currentTags contains "in-progress" then
-        set oldDelimiters to AppleScript's text item delimiters
-        set AppleScript's text item delimiters to ", "
-        set tagList to text items of currentTags
-        set newTagList to {{}}
-        repeat with i from 1 to (count of tagList)
-            set tagItem to (item i of tagList) as text
-            if tagItem is not "in-progress" then
-                set end of newTagList to tagItem
-            end if
-        end repeat
-        if (count of newTagList) > 0 then
-            set AppleScript's text item delimiters to ", "
-            set tag names of todoToComplete to (newTagList as text)
-        else
-            set tag names of todoToComplete to ""
-        end if
-        set AppleScript's text item delimiters to oldDelimiters
-    end if
-
-    set status of todoToComplete to completed
-    return todoName
-end tell
-"#,
-        list_name, FILTER_COMPLETED, todo_num, todo_num, todo_num
-    );
-
-    match run_applescript(&script) {
-        Ok(todo_name) => {
-            println!("Completed: {}", todo_name.trim());
-        }
-        Err(error) => {
-            eprintln!("Error completing todo: {}", error);
-            std::process::exit(1);
+pub fn show_projects(backend: &dyn Backend, format: Format) -> Result<()> {
+    let projects = fetch_lists_of_kind(backend, ListKind::Project)?;
+    if format != Format::Text {
+        print!("{}", output::render_lists(&projects, format));
+    } else if projects.is_empty() {
+        println!("No projects");
+    } else {
+        println!("Projects:");
+        for project in &projects {
+            print_list_line(project);
         }
     }
+    Ok(())
 }
 
-pub fn move_todo(args: &[String]) {
-    if args.is_empty() {
-        eprintln!("Error: 'mv' command requires todo identifier or number");
-        eprintln!("Usage: thingy mv <id>");
-        eprintln!("       thingy mv <from> <id> [to]");
-        std::process::exit(1);
-    }
-
-    let (from_list, id_str, to_list) = if args.len() == 1 {
-        ("Inbox", &args[0], "Today")
-    } else if args.len() == 2 {
-        let from = parse_list_name(&args[0]).unwrap_or_else(|e| {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        });
-        (from, &args[1], "Today")
-    } else {
-        let from = parse_list_name(&args[0]).unwrap_or_else(|e| {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        });
-        let to = parse_list_name(&args[2]).unwrap_or_else(|e| {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        });
-        (from, &args[1], to)
+fn find_project(backend: &dyn Backend, id_str: &str) -> Result<ListInfo> {
+    let projects = fetch_lists_of_kind(backend, ListKind::Project)?;
+    resolve_list_info(&projects, id_str, "project")
+}
+
+pub fn show_project(backend: &dyn Backend, id_str: &str, format: Format) -> Result<()> {
+    let project = find_project(backend, id_str)?;
+    show_list(backend, &project.name, format)
+}
+
+pub fn complete_project(backend: &dyn Backend, id_str: &str) -> Result<()> {
+    let project = find_project(backend, id_str)?;
+    let name = backend.complete_project(&project.id)?;
+    println!("Completed project: {}", name);
+    Ok(())
+}
+
+pub fn remove_project(backend: &dyn Backend, id_str: &str) -> Result<()> {
+    let project = find_project(backend, id_str)?;
+    let name = backend.delete_project(&project.id)?;
+    println!("Removed project: {}", name);
+    Ok(())
+}
+
+/// Creates project `name`, in the area `area` names (by identifier or
+/// name) if given.
+pub fn new_project(backend: &dyn Backend, name: &str, area: Option<&str>) -> Result<()> {
+    let area = match area {
+        Some(area_name) => {
+            let areas = fetch_lists_of_kind(backend, ListKind::Area)?;
+            Some(match find_list(&areas, area_name) {
+                Some(found) => found.name.clone(),
+                None => area_name.to_string(),
+            })
+        }
+        None => None,
     };
+    let name = backend.create_project(name, area.as_deref())?;
+    println!("Created project: {}", name);
+    Ok(())
+}
 
-    let todos = fetch_todos_for_list(from_list);
-    let num = resolve_id(&todos, id_str, from_list);
-
-    let script = format!(
-        r#"
-tell application "Things3"
-    set fromList to list "{}"
-    set toList to list "{}"
-    set listToQuery to fromList
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoToMove to item {} of listTodos
-    set todoName to name of todoToMove
-    move todoToMove to toList
-    return todoName
-end tell
-"#,
-        from_list, to_list, FILTER_COMPLETED, num, num, num
-    );
-
-    match run_applescript(&script) {
-        Ok(todo_name) => {
-            println!(
-                "Moved from {} to {}: {}",
-                from_list,
-                to_list,
-                todo_name.trim()
-            );
+pub fn show_areas(backend: &dyn Backend, format: Format) -> Result<()> {
+    let areas = fetch_lists_of_kind(backend, ListKind::Area)?;
+    if format != Format::Text {
+        print!("{}", output::render_lists(&areas, format));
+    } else if areas.is_empty() {
+        println!("No areas");
+    } else {
+        println!("Areas:");
+        for area in &areas {
+            print_list_line(area);
         }
-        Err(error) => {
-            eprintln!("Error moving todo: {}", error);
-            std::process::exit(1);
+    }
+    Ok(())
+}
+
+pub fn show_area(backend: &dyn Backend, id_str: &str, format: Format) -> Result<()> {
+    let areas = fetch_lists_of_kind(backend, ListKind::Area)?;
+    let area = resolve_list_info(&areas, id_str, "area")?;
+    if format == Format::Text {
+        let projects = fetch_lists_of_kind(backend, ListKind::Project)?;
+        let in_area: Vec<&ListInfo> = projects
+            .iter()
+            .filter(|p| p.area.as_deref() == Some(area.name.as_str()))
+            .collect();
+        if !in_area.is_empty() {
+            println!("{} projects:", area.name);
+            for project in in_area {
+                println!(" {} {}", project.identifier, project.name);
+            }
         }
     }
+    show_list(backend, &area.name, format)
 }
 
-pub fn show_inbox() {
-    show_list("Inbox");
+/// Splits `<id...> <tag...>`: leading arguments naming todos are ids, the
+/// rest tags.  A `#` prefix marks a tag that looks like an identifier.
+fn split_ids_and_tags<'a>(
+    todos: &'a [Todo],
+    args: &[String],
+) -> Result<(Vec<&'a Todo>, Vec<String>)> {
+    let mut selected: Vec<&Todo> = Vec::new();
+    let mut i = 0;
+    // Always leave at least one argument for the tags:
+    while i + 1 < args.len() && !args[i].starts_with('#') {
+        let Some(todo) = find_todo(todos, &args[i]) else {
+            break;
+        };
+        if !selected.iter().any(|t| t.id == todo.id) {
+            selected.push(todo);
+        }
+        i += 1;
+    }
+
+    if selected.is_empty() {
+        return Err(match args.first() {
+            Some(id_str) => ThingyError::not_found(
+                format!("No todo found with identifier or number '{}'", id_str),
+                None,
+            ),
+            None => missing_identifier(),
+        });
+    }
+
+    let tags: Vec<String> = args[i..]
+        .iter()
+        .map(|t| t.strip_prefix('#').unwrap_or(t).to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if tags.is_empty() {
+        return Err(ThingyError::usage("Missing tag", None));
+    }
+    Ok((selected, tags))
+}
+
+fn edit_tags_command(
+    backend: &dyn Backend,
+    args: &[String],
+    adding: bool,
+    mode: Mode,
+) -> Result<()> {
+    let command = if adding { "tag" } else { "untag" };
+    if args.len() < 2 {
+        return Err(ThingyError::usage(
+            format!("'{}' command requires todo identifiers and tags", command),
+            format!("thingy {} [list] <id...> <tag...>", command),
+        ));
+    }
+
+    let (list_name, rest) = split_leading_list(backend, args)?;
+    let lists = lookup_lists(list_name);
+    let todos = fetch_todos_in_lists(backend, &lists)?;
+    let (selected, tags) = split_ids_and_tags(&todos, rest).map_err(|error| match error {
+        ThingyError::NotFound { message, .. } => ThingyError::not_found(message, ls_hint(&lists)),
+        other => other,
+    })?;
+
+    let selected: Vec<Todo> = selected.into_iter().cloned().collect();
+    let verb = if adding { "Tagged" } else { "Untagged" };
+    apply_to_todos(
+        backend,
+        mode,
+        command,
+        &selected,
+        |todo| Op::EditTags {
+            id: todo.id.clone(),
+            add: if adding { tags.clone() } else { Vec::new() },
+            remove: if adding { Vec::new() } else { tags.clone() },
+        },
+        |_, name| format!("{} {}: {}", verb, tags.join(", "), name),
+    )
 }
 
-pub fn show_today() {
-    show_list("Today");
+pub fn tag_todos(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    edit_tags_command(backend, args, true, mode)
 }
 
-pub fn count_todos() {
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    {}
-    return count of listTodos
-end tell
-"#,
-        FILTER_COMPLETED
-    );
+pub fn untag_todos(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    edit_tags_command(backend, args, false, mode)
+}
 
-    match run_applescript(&script) {
-        Ok(count_str) => {
-            let count = count_str.trim();
-            println!("{} todo{}", count, if count == "1" { "" } else { "s" });
-        }
-        Err(error) => {
-            eprintln!("Error counting todos: {}", error);
-            std::process::exit(1);
-        }
+pub fn show_tags(backend: &dyn Backend, format: Format) -> Result<()> {
+    let tags = backend.fetch_tags()?;
+    if format != Format::Text {
+        print!("{}", output::render_tags(&tags, format));
+        return Ok(());
     }
+    if tags.is_empty() {
+        println!("No tags");
+    }
+    for line in tag_tree_lines(&tags) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+pub fn count_todos(backend: &dyn Backend, format: Format) -> Result<()> {
+    let list_name = default_list();
+    let count = backend.count_todos(&list_name)?;
+    if format != Format::Text {
+        print!("{}", output::render_count(&list_name, count, format));
+        return Ok(());
+    }
+    println!("{} todo{}", count, if count == 1 { "" } else { "s" });
+    Ok(())
 }
 
-pub fn show_inprog() {
-    let todos = fetch_todos_for_list("Today");
+pub fn show_inprog(backend: &dyn Backend, format: Format) -> Result<()> {
+    let todos = backend.fetch_todos(&default_list())?;
     let inprog_todos: Vec<&Todo> = todos
         .iter()
-        .filter(|t| t.tags.contains("in-progress"))
+        .filter(|t| t.has_tag(&config::get().tags.in_progress))
         .collect();
 
+    if print_structured(&inprog_todos, format) {
+        return Ok(());
+    }
+
     if inprog_todos.is_empty() {
         println!("No in-progress todos");
     } else {
@@ -517,23 +883,34 @@ pub fn show_inprog() {
             print_todo_line(todo);
         }
     }
+    Ok(())
 }
 
-pub fn show_completed() {
-    let todos = fetch_completed_todos();
+pub fn show_completed(backend: &dyn Backend, format: Format) -> Result<()> {
+    let list_name = default_list();
+    let todos = backend.fetch_completed(&list_name)?;
+
+    if print_structured(&todos.iter().collect::<Vec<_>>(), format) {
+        return Ok(());
+    }
 
     if todos.is_empty() {
-        println!("No completed todos today");
+        println!("No completed todos in {}", list_name);
     } else {
-        println!("Completed today:");
+        println!("Completed in {}:", list_name);
         for todo in todos {
             print_todo_line(&todo);
         }
     }
+    Ok(())
 }
 
-fn show_list(list_name: &str) {
-    let todos = fetch_todos_for_list(list_name);
+fn show_list(backend: &dyn Backend, list_name: &str, format: Format) -> Result<()> {
+    let todos = backend.fetch_todos(list_name)?;
+
+    if print_structured(&todos.iter().collect::<Vec<_>>(), format) {
+        return Ok(());
+    }
 
     if todos.is_empty() {
         println!("{} is empty", list_name);
@@ -543,261 +920,127 @@ fn show_list(list_name: &str) {
             print_todo_line(&todo);
         }
     }
+    Ok(())
 }
 
-fn mark_todo_inprogress(list_name: &str, todo_num: usize) -> String {
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "{}"
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoToTag to item {} of listTodos
-    set todoName to name of todoToTag
-
-    set inProgressTag to missing value
-    try
-        set inProgressTag to tag "in-progress"
-    on error
-        set inProgressTag to make new tag with properties {{name:"in-progress"}}
-    end try
-
-    set currentTags to tag names of todoToTag
-    if currentTags is "" then
-        set tag names of todoToTag to "in-progress"
-    else if currentTags does not contain "in-progress" then
-        set tag names of todoToTag to currentTags & ", in-progress"
-    end if
-    return todoName
-end tell
-"#,
-        list_name, FILTER_COMPLETED, todo_num, todo_num, todo_num
-    );
-
-    match run_applescript(&script) {
-        Ok(todo_name) => todo_name,
-        Err(error) => {
-            eprintln!("Error tagging todo: {}", error);
-            std::process::exit(1);
-        }
+/// An op adding `tag` to a todo.
+fn add_tag_op(tag: &str) -> impl Fn(&Todo) -> Op + '_ {
+    move |todo| Op::EditTags {
+        id: todo.id.clone(),
+        add: vec![tag.to_string()],
+        remove: Vec::new(),
     }
 }
 
-pub fn workon_todo(args: &[String]) {
-    let list_name = if args.len() >= 2 {
-        match args[0].to_lowercase().as_str() {
-            "inbox" => "Inbox",
-            "today" => "Today",
-            _ => "Today",
-        }
-    } else {
-        "Today"
-    };
-
-    let todos = fetch_todos_for_list(list_name);
-    let (list_name, todo_num) = parse_list_and_identifier(args, &todos);
-    let todo_name = mark_todo_inprogress(list_name, todo_num);
-    println!("Working on: {}", todo_name.trim());
+pub fn workon_todo(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    let todos = parse_list_and_identifiers(backend, args)?;
+    apply_to_todos(
+        backend,
+        mode,
+        "workon",
+        &todos,
+        add_tag_op(&config::get().tags.in_progress),
+        |_, name| format!("Working on: {}", name),
+    )
 }
 
-pub fn next_todo(args: &[String]) {
+pub fn next_todo(backend: &dyn Backend, args: &[String], format: Format, mode: Mode) -> Result<()> {
     if args.is_empty() {
-        show_next_todo();
+        show_next_todo(backend, format)
     } else {
-        tag_next_todo(args);
+        tag_next_todo(backend, args, mode)
     }
 }
 
-fn tag_next_todo(args: &[String]) {
-    let list_name = if args.len() >= 2 {
-        match args[0].to_lowercase().as_str() {
-            "inbox" => "Inbox",
-            "today" => "Today",
-            _ => "Today",
-        }
+fn tag_next_todo(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    let todos = parse_list_and_identifiers(backend, args)?;
+    apply_to_todos(
+        backend,
+        mode,
+        "next",
+        &todos,
+        add_tag_op(&config::get().tags.on_deck),
+        |_, name| format!("Tagged as next: {}", name),
+    )
+}
+
+fn show_next_todo(backend: &dyn Backend, format: Format) -> Result<()> {
+    let list_name = default_list();
+    let todos = backend.fetch_todos(&list_name)?;
+    let on_deck: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| t.has_tag(&config::get().tags.on_deck))
+        .collect();
+
+    let shown: Vec<&Todo> = if on_deck.is_empty() {
+        todos.iter().take(1).collect()
     } else {
-        "Today"
+        on_deck.clone()
     };
-
-    let todos = fetch_todos_for_list(list_name);
-    let (list_name, todo_num) = parse_list_and_identifier(args, &todos);
-
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "{}"
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoToTag to item {} of listTodos
-    set todoName to name of todoToTag
-
-    set onDeckTag to missing value
-    try
-        set onDeckTag to tag "on-deck"
-    on error
-        set onDeckTag to make new tag with properties {{name:"on-deck"}}
-    end try
-
-    set currentTags to tag names of todoToTag
-    if currentTags is "" then
-        set tag names of todoToTag to "on-deck"
-    else if currentTags does not contain "on-deck" then
-        set tag names of todoToTag to currentTags & ", on-deck"
-    end if
-    return todoName
-end tell
-"#,
-        list_name, FILTER_COMPLETED, todo_num, todo_num, todo_num
-    );
-
-    match run_applescript(&script) {
-        Ok(todo_name) => {
-            println!("Tagged as next: {}", todo_name.trim());
-        }
-        Err(error) => {
-            eprintln!("Error tagging todo: {}", error);
-            std::process::exit(1);
-        }
+    if print_structured(&shown, format) {
+        return Ok(());
     }
-}
 
-fn show_next_todo() {
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    {}
-    set output to ""
-    set oldDelimiters to AppleScript's text item delimiters
-    repeat with todo in listTodos
-        set todoTags to tag names of todo
-        if todoTags contains "on-deck" then
-            set todoName to name of todo
-            if (count of todoTags) > 0 then
-                set AppleScript's text item delimiters to ", "
-                set tagString to todoTags as string
-                set AppleScript's text item delimiters to oldDelimiters
-                set output to output & todoName & " [" & tagString & "]" & "\n"
-            else
-                set output to output & todoName & "\n"
-            end if
-        end if
-    end repeat
-    set AppleScript's text item delimiters to oldDelimiters
-    return output
-end tell
-"#,
-        FILTER_COMPLETED
-    );
-
-    match run_applescript(&script) {
-        Ok(todos) => {
-            let trimmed = todos.trim();
-            if trimmed.is_empty() {
-                // No on-deck todos found, fall back to showing the top item:
-                let todos = fetch_todos_for_list("Today");
-                if todos.is_empty() {
-                    println!("No todos in Today list");
-                } else {
-                    let first = &todos[0];
-                    println!("{}", todo_display_text(first));
-                }
-            } else {
-                for todo in trimmed.lines() {
-                    println!("{}", todo);
-                }
-            }
-        }
-        Err(error) => {
-            eprintln!("Error querying Things: {}", error);
-            std::process::exit(1);
+    if !on_deck.is_empty() {
+        for todo in on_deck {
+            println!("{}", todo_display_text(todo));
         }
+    } else if let Some(first) = todos.first() {
+        // No on-deck todos found, fall back to showing the top item:
+        println!("{}", todo_display_text(first));
+    } else {
+        println!("No todos in {} list", list_name);
     }
+    Ok(())
 }
 
-pub fn rand_todo() {
-    let todos = fetch_todos_for_list("Today");
+pub fn rand_todo(backend: &dyn Backend) -> Result<()> {
+    let list_name = default_list();
+    let todos = backend.fetch_todos(&list_name)?;
 
     if todos.is_empty() {
-        println!("No todos in Today list");
-        std::process::exit(0);
+        println!("No todos in {} list", list_name);
+        return Ok(());
     }
 
     let mut rng = rand::thread_rng();
     let random_idx = rng.gen_range(0..todos.len());
-    let selected_todo = &todos[random_idx];
 
-    let todo_name = mark_todo_inprogress("Today", selected_todo.index);
-
-    println!("You are working on:\n");
-    println!("    [{}] {}\n", selected_todo.identifier, todo_name.trim());
+    apply_to_todos(
+        backend,
+        Mode::Apply,
+        "rand",
+        &todos[random_idx..=random_idx],
+        add_tag_op(&config::get().tags.in_progress),
+        |todo, name| format!("You are working on:\n\n    [{}] {}\n", todo.identifier, name),
+    )?;
     println!("Either:\n");
     println!("- do it now");
     println!("- spend five minutes on it and schedule it later");
     println!("- delete the todo");
     println!("- move it out of today into the \"whenever\" bucket");
+    Ok(())
 }
 
-pub fn fetch_todo_notes(list_name: &str, todo_num: usize) -> Result<String, String> {
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "{}"
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoItem to item {} of listTodos
-    return notes of todoItem
-end tell
-"#,
-        list_name, FILTER_COMPLETED, todo_num, todo_num, todo_num
-    );
-
-    run_applescript(&script)
-}
-
-pub fn show_todo_notes(args: &[String]) {
-    let list_name = if args.len() >= 2 {
-        match args[0].to_lowercase().as_str() {
-            "inbox" => "Inbox",
-            "today" => "Today",
-            _ => "Today",
-        }
-    } else {
-        "Today"
-    };
+pub fn show_todo_notes(backend: &dyn Backend, args: &[String]) -> Result<()> {
+    let (_, todo) = parse_list_and_identifier(backend, args)?;
 
-    let todos = fetch_todos_for_list(list_name);
-    let (list_name, todo_num) = parse_list_and_identifier(args, &todos);
-
-    match fetch_todo_notes(list_name, todo_num) {
-        Ok(notes) => {
-            let todo = &todos.iter().find(|t| t.index == todo_num).unwrap();
-            println!("{}", todo.name);
-            let trimmed_notes = notes.trim();
-            if !trimmed_notes.is_empty() {
-                println!();
-                println!("{}", trimmed_notes);
-            } else {
-                println!();
-                println!("(no notes)");
-            }
-        }
-        Err(error) => {
-            eprintln!("Error fetching notes: {}", error);
-            std::process::exit(1);
-        }
+    let notes = backend.fetch_notes(&todo.id)?;
+    println!("{}", todo.name);
+    let trimmed_notes = notes.trim();
+    if !trimmed_notes.is_empty() {
+        println!();
+        println!("{}", trimmed_notes);
+    } else {
+        println!();
+        println!("(no notes)");
     }
+    Ok(())
 }
 
 fn todo_display_text(todo: &Todo) -> String {
     if !todo.tags.is_empty() {
-        format!("{} [{}]", todo.name, todo.tags)
+        format!("{} [{}]", todo.name, todo.tag_text())
     } else {
         todo.name.clone()
     }
@@ -807,44 +1050,26 @@ fn print_todo_line(todo: &Todo) {
     println!(" {} {}", todo.identifier, todo_display_text(todo));
 }
 
-fn parse_time_seconds(s: &str) -> Option<u64> {
-    let s = s.trim();
-    if s.is_empty() {
-        return None;
-    }
-    let num_end = s.find(|c: char| !c.is_ascii_digit())?;
-    if num_end == 0 {
-        return None;
-    }
-    let num: u64 = s[..num_end].parse().ok()?;
-    let unit = s[num_end..].trim().to_lowercase();
-    match unit.as_str() {
-        "s" | "sec" | "secs" | "second" | "seconds" => Some(num),
-        "m" | "min" | "mins" | "minute" | "minutes" => Some(num * 60),
-        "h" | "hr" | "hrs" | "hour" | "hours" => Some(num * 3600),
-        "d" | "day" | "days" => Some(num * 86400),
-        _ => None,
-    }
-}
-
-fn todo_time_secs(todo: &Todo) -> Option<u64> {
-    if todo.tags.is_empty() {
-        return None;
-    }
-    for tag in todo.tags.split(',') {
-        if let Some(secs) = parse_time_seconds(tag.trim()) {
-            return Some(secs);
-        }
+/// Prints `todos` as JSON or TSV and returns true, or returns false if
+/// the command should print its own text output.
+fn print_structured(todos: &[&Todo], format: Format) -> bool {
+    if format == Format::Text {
+        return false;
     }
-    None
+    print!("{}", output::render(todos, format));
+    true
 }
 
-pub fn show_untagged() {
-    let todos = fetch_todos_for_list("Today");
+pub fn show_untagged(backend: &dyn Backend, format: Format) -> Result<()> {
+    let todos = backend.fetch_todos(&default_list())?;
     let untagged: Vec<&Todo> = todos.iter()
         .filter(|t| t.tags.is_empty())
         .collect();
 
+    if print_structured(&untagged, format) {
+        return Ok(());
+    }
+
     if untagged.is_empty() {
         println!("No untagged todos");
     } else {
@@ -852,167 +1077,131 @@ pub fn show_untagged() {
             print_todo_line(todo);
         }
     }
+    Ok(())
 }
 
-pub fn soonest_todo() {
-    let todos = fetch_todos_for_list("Today");
+pub fn soonest_todo(backend: &dyn Backend, format: Format) -> Result<()> {
+    let list_name = default_list();
+    let todos = backend.fetch_todos(&list_name)?;
 
     if todos.is_empty() {
-        println!("No todos in Today list");
-        return;
+        if !print_structured(&[], format) {
+            println!("No todos in {} list", list_name);
+        }
+        return Ok(());
     }
 
     let untagged: Vec<&Todo> = todos.iter()
-        .filter(|t| todo_time_secs(t).is_none())
+        .filter(|t| t.time_secs().is_none())
         .collect();
 
     if !untagged.is_empty() {
+        if print_structured(&untagged, format) {
+            return Ok(());
+        }
         for todo in &untagged {
             print_todo_line(todo);
         }
-        return;
+        return Ok(());
     }
 
     // All todos have time tags - find the soonest:
     let mut tagged: Vec<(&Todo, u64)> = todos.iter()
-        .filter_map(|t| todo_time_secs(t).map(|secs| (t, secs)))
+        .filter_map(|t| t.time_secs().map(|secs| (t, secs)))
         .collect();
 
     tagged.sort_by(|a, b| {
         a.1.cmp(&b.1).then(a.0.name.len().cmp(&b.0.name.len()))
     });
 
-    if let Some((todo, _)) = tagged.first() {
+    let soonest: Vec<&Todo> = tagged.iter().take(1).map(|(t, _)| *t).collect();
+    if print_structured(&soonest, format) {
+        return Ok(());
+    }
+    if let Some(todo) = soonest.first() {
         print_todo_line(todo);
     }
+    Ok(())
 }
 
-pub fn defer_todo(args: &[String]) {
-    let todos = fetch_todos_for_list("Today");
-    let (_, num) = parse_list_and_identifier(args, &todos);
-
-    let script = format!(
-        r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    {}
-    if (count of listTodos) < {} then
-        error "Todo number {} is out of range"
-    end if
-    set todoToDefer to item {} of listTodos
-    set todoName to name of todoToDefer
-    schedule todoToDefer for (current date) + (1 * days)
-    return todoName
-end tell
-"#,
-        FILTER_COMPLETED, num, num, num
-    );
-
-    match run_applescript(&script) {
-        Ok(todo_name) => {
-            println!("Deferred to tomorrow: {}", todo_name.trim());
-        }
-        Err(error) => {
-            eprintln!("Error deferring todo: {}", error);
-            std::process::exit(1);
-        }
-    }
+pub fn defer_todo(backend: &dyn Backend, args: &[String], mode: Mode) -> Result<()> {
+    let todos = parse_list_and_identifiers(backend, args)?;
+    apply_to_todos(
+        backend,
+        mode,
+        "defer",
+        &todos,
+        |todo| Op::Defer { id: todo.id.clone() },
+        |_, name| format!("Deferred to tomorrow: {}", name),
+    )
 }
 
-pub fn show_log(args: &[String]) {
-    let days: i32 = if args.is_empty() {
-        1
+pub fn show_log(backend: &dyn Backend, days: u32, format: Format) -> Result<()> {
+    let todos = backend.fetch_logbook(days)?;
+    if print_structured(&todos.iter().collect::<Vec<_>>(), format) {
+        return Ok(());
+    }
+    if todos.is_empty() {
+        println!("No logbook entries in the last {} day{}", days, if days == 1 { "" } else { "s" });
     } else {
-        match args[0].parse() {
-            Ok(n) if n > 0 => n,
-            _ => {
-                eprintln!("Error: days must be a positive number");
-                eprintln!("Usage: thingy log [days]");
-                std::process::exit(1);
-            }
-        }
-    };
-
-    let script = format!(
-        r#"
-tell application "Things3"
-    set targetDate to (current date) - ({} * days)
-    set logbookTodos to to dos of list "Logbook"
-    set output to ""
-    set oldDelimiters to AppleScript's text item delimiters
-
-    repeat with i from 1 to (count of logbookTodos)
-        set todo to item i of logbookTodos
-        set completionDate to completion date of todo
-        if completionDate is not missing value then
-            if completionDate ≥ targetDate then
-                set todoName to name of todo
-                set todoTags to tag names of todo
-                if (count of todoTags) > 0 then
-                    set AppleScript's text item delimiters to ", "
-                    set tagString to todoTags as string
-                    set AppleScript's text item delimiters to oldDelimiters
-                    set output to output & todoName & "|" & tagString & "\n"
-                else
-                    set output to output & todoName & "|\n"
-                end if
-            else if completionDate < targetDate then
-                exit repeat
-            end if
-        end if
-    end repeat
-
-    set AppleScript's text item delimiters to oldDelimiters
-    return output
-end tell
-"#,
-        days
-    );
-
-    match run_applescript(&script) {
-        Ok(result) => {
-            let trimmed = result.trim();
-            if trimmed.is_empty() {
-                println!("No logbook entries in the last {} day{}", days, if days == 1 { "" } else { "s" });
-            } else {
-                for line in trimmed.lines() {
-                    let parts: Vec<&str> = line.split('|').collect();
-                    if parts.len() >= 1 {
-                        let name = parts[0];
-                        if parts.len() >= 2 && !parts[1].is_empty() {
-                            println!("  {} [{}]", name, parts[1]);
-                        } else {
-                            println!("  {}", name);
-                        }
-                    }
-                }
-            }
-        }
-        Err(error) => {
-            eprintln!("Error fetching logbook: {}", error);
-            std::process::exit(1);
+        for todo in todos {
+            println!("  {}", todo_display_text(&todo));
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::file_backend::FileBackend;
+    use crate::todo::NewTodo;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn backend_with(list_name: &str, names: &[&str]) -> FileBackend {
+        let backend = FileBackend::in_memory();
+        for name in names {
+            backend.add_todo(list_name, &NewTodo::new(name)).unwrap();
+        }
+        backend
+    }
+
+    fn names(backend: &FileBackend, list_name: &str) -> Vec<String> {
+        backend
+            .fetch_todos(list_name)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect()
+    }
+
+    fn today() -> Vec<String> {
+        vec!["Today".to_string()]
+    }
 
     fn make_todo(name: &str, identifier: &str, index: usize) -> Todo {
         Todo {
+            id: format!("id-{}", index),
             name: name.to_string(),
-            tags: String::new(),
+            tags: Vec::new(),
             is_completed: false,
             index,
             identifier: identifier.to_string(),
+            ..Default::default()
         }
     }
 
     #[test]
     fn test_resolve_id_numeric() {
-        let todos = vec![make_todo("Buy milk", "BUY", 1)];
-        assert_eq!(resolve_id(&todos, "3", "Today"), 3);
+        let todos = vec![
+            make_todo("Buy milk", "BUY", 1),
+            make_todo("Call mom", "CAL", 2),
+            make_todo("Fix bike", "FIX", 3),
+        ];
+        assert_eq!(resolve_id(&todos, "3", &today()).unwrap().id, "id-3");
     }
 
     #[test]
@@ -1021,48 +1210,358 @@ mod tests {
             make_todo("Buy milk", "BUY", 1),
             make_todo("Call mom", "CAL", 2),
         ];
-        assert_eq!(resolve_id(&todos, "CAL", "Today"), 2);
+        assert_eq!(resolve_id(&todos, "CAL", &today()).unwrap().id, "id-2");
     }
 
     #[test]
     fn test_resolve_id_case_insensitive() {
         let todos = vec![make_todo("Buy milk", "BUY", 1)];
-        assert_eq!(resolve_id(&todos, "buy", "Today"), 1);
+        assert_eq!(resolve_id(&todos, "buy", &today()).unwrap().id, "id-1");
     }
 
     #[test]
-    fn test_parse_time_seconds_minutes() {
-        assert_eq!(parse_time_seconds("5m"), Some(300));
-        assert_eq!(parse_time_seconds("2min"), Some(120));
-        assert_eq!(parse_time_seconds("1minute"), Some(60));
-        assert_eq!(parse_time_seconds("3minutes"), Some(180));
+    fn test_resolve_id_by_name() {
+        let todos = vec![
+            make_todo("Buy milk", "BUY", 1),
+            make_todo("Call mom", "CAL", 2),
+        ];
+        assert_eq!(resolve_id(&todos, "mom", &today()).unwrap().id, "id-2");
+        // A lone fuzzy match still has to be confirmed:
+        let error = resolve_id(&todos, "bymlk", &today()).unwrap_err();
+        assert!(matches!(error, ThingyError::AmbiguousId { .. }));
     }
 
     #[test]
-    fn test_parse_time_seconds_hours() {
-        assert_eq!(parse_time_seconds("1h"), Some(3600));
-        assert_eq!(parse_time_seconds("2hr"), Some(7200));
-        assert_eq!(parse_time_seconds("3hours"), Some(10800));
+    fn test_resolve_id_number_is_a_position() {
+        let todos = vec![make_todo("Buy milk", "BUY", 1), make_todo("Meet at 5pm", "MEE", 2)];
+        let error = resolve_id(&todos, "5", &today()).unwrap_err();
+        assert_eq!(error.to_string(), "No todo at position 5 in Today");
+    }
+
+    #[test]
+    fn test_complete_todo_multiple() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom", "Fix bike"]);
+        complete_todo(&backend, &args(&["buy", "FIX"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Today"), vec!["Call mom"]);
+        assert_eq!(backend.fetch_completed("Today").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_complete_todo_mixed_numbers_and_identifiers() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom", "Fix bike"]);
+        complete_todo(&backend, &args(&["3", "BUY", "1"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Today"), vec!["Call mom"]);
+        assert_eq!(backend.fetch_completed("Today").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_complete_todo_drops_inprogress_tag() {
+        let backend = backend_with("Today", &["Buy milk"]);
+        workon_todo(&backend, &args(&["BUY"]), Mode::Apply).unwrap();
+        complete_todo(&backend, &args(&["BUY"]), Mode::Apply).unwrap();
+        let completed = backend.fetch_completed("Today").unwrap();
+        assert!(completed[0].tags.is_empty());
+    }
+
+    #[test]
+    fn test_identifiers_found_in_any_visible_list() {
+        let backend = backend_with("Inbox", &["Call mom", "Buy milk"]);
+        backend.add_todo("Today", &NewTodo::new("Buy bread")).unwrap();
+
+        let todos = fetch_todos_in_lists(&backend, &visible_lists()).unwrap();
+        let identifiers: Vec<&str> = todos.iter().map(|t| t.identifier.as_str()).collect();
+        assert_eq!(identifiers, vec!["BUY", "CAL", "BU1"]);
+
+        complete_todo(&backend, &args(&["CAL", "BU1"]), Mode::Apply).unwrap();
+        assert!(names(&backend, "Inbox").is_empty());
+        assert_eq!(names(&backend, "Today"), vec!["Buy bread"]);
+
+        // Numbers are positions in the default list:
+        complete_todo(&backend, &args(&["1"]), Mode::Apply).unwrap();
+        assert!(names(&backend, "Today").is_empty());
+    }
+
+    #[test]
+    fn test_move_todo_inbox_to_today() {
+        let backend = backend_with("Inbox", &["Buy milk", "Call mom"]);
+        move_todo(&backend, &args(&["CAL"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Inbox"), vec!["Buy milk"]);
+        assert_eq!(names(&backend, "Today"), vec!["Call mom"]);
+    }
+
+    #[test]
+    fn test_batch_commands() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom", "Fix bike", "Read book"]);
+        workon_todo(&backend, &args(&["BUY", "CAL"]), Mode::Apply).unwrap();
+        defer_todo(&backend, &args(&["FIX", "REA"]), Mode::Apply).unwrap();
+        remove_todo(&backend, &args(&["CAL"]), Mode::Apply).unwrap();
+
+        let todos = backend.fetch_todos("Today").unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].tags, vec!["in-progress"]);
+        assert_eq!(names(&backend, "Upcoming"), vec!["Fix bike", "Read book"]);
+    }
+
+    #[test]
+    fn test_move_several_todos() {
+        let backend = backend_with("Inbox", &["Buy milk", "Call mom", "Fix bike"]);
+        move_todo(&backend, &args(&["inbox", "BUY", "FIX", "someday"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Inbox"), vec!["Call mom"]);
+        assert_eq!(names(&backend, "Someday"), vec!["Buy milk", "Fix bike"]);
+    }
+
+    #[test]
+    fn test_move_without_destination() {
+        let backend = backend_with("Inbox", &["Buy milk", "Call mom", "Fix bike"]);
+        move_todo(&backend, &args(&["inbox", "BUY", "CAL"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Inbox"), vec!["Fix bike"]);
+        assert_eq!(names(&backend, "Today"), vec!["Buy milk", "Call mom"]);
+    }
+
+    #[test]
+    fn test_move_finds_todos_across_lists() {
+        let backend = backend_with("Today", &["Buy milk"]);
+        backend.add_todo("Inbox", &NewTodo::new("Call mom")).unwrap();
+        backend.add_todo("Inbox", &NewTodo::new("Fix bike")).unwrap();
+        move_todo(&backend, &args(&["BUY", "CAL", "someday"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Someday"), vec!["Buy milk", "Call mom"]);
+
+        move_todo(&backend, &args(&["FIX"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Today"), vec!["Fix bike"]);
+    }
+
+    #[test]
+    fn test_numbers_refer_to_the_first_list() {
+        let backend = backend_with("Today", &["Buy milk"]);
+        for name in ["Call mom", "Fix bike", "Wash car", "Read book"] {
+            backend.add_todo("Inbox", &NewTodo::new(name)).unwrap();
+        }
+        let error = complete_todo(&backend, &args(&["3"]), Mode::Apply).unwrap_err();
+        assert!(matches!(error, ThingyError::NotFound { .. }));
+        assert_eq!(names(&backend, "Inbox").len(), 4);
+
+        complete_todo(&backend, &args(&["1"]), Mode::Apply).unwrap();
+        assert!(names(&backend, "Today").is_empty());
+        assert_eq!(names(&backend, "Inbox").len(), 4);
+    }
+
+    #[test]
+    fn test_move_identifiers_that_look_like_lists() {
+        let backend = backend_with("Inbox", &["Buy milk", "Some stuff", "Trash bags", "Pay rent"]);
+        backend.create_project("Payroll", None).unwrap();
+        move_todo(&backend, &args(&["BUY", "SOM"]), Mode::Apply).unwrap();
+        move_todo(&backend, &args(&["inbox", "TRA"]), Mode::Apply).unwrap();
+        move_todo(&backend, &args(&["PAY", "today"]), Mode::Apply).unwrap();
+        assert!(names(&backend, "Inbox").is_empty());
+        assert!(names(&backend, "Someday").is_empty());
+        assert!(names(&backend, "Trash").is_empty());
+        assert_eq!(names(&backend, "Today"), vec!["Buy milk", "Some stuff", "Trash bags", "Pay rent"]);
+    }
+
+    #[test]
+    fn test_move_todo_between_lists() {
+        let backend = backend_with("Today", &["Buy milk"]);
+        move_todo(&backend, &args(&["today", "BUY", "inbox"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Inbox"), vec!["Buy milk"]);
+    }
+
+    #[test]
+    fn test_defer_todo() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom"]);
+        defer_todo(&backend, &args(&["BUY"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Today"), vec!["Call mom"]);
+        assert_eq!(names(&backend, "Upcoming"), vec!["Buy milk"]);
+    }
+
+    #[test]
+    fn test_remove_todo_from_inbox() {
+        let backend = backend_with("Inbox", &["Buy milk", "Call mom"]);
+        remove_todo(&backend, &args(&["inbox", "BUY"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Inbox"), vec!["Call mom"]);
+    }
+
+    #[test]
+    fn test_errors_by_class() {
+        let backend = backend_with("Inbox", &["Buy milk", "Call mom"]);
+        let error = remove_todo(&backend, &args(&["inbox", "ZZZ"]), Mode::Apply).unwrap_err();
+        assert!(matches!(error, ThingyError::NotFound { .. }));
+        assert_eq!(error.exit_code(), 3);
+
+        let error = complete_todo(&backend, &[], Mode::Apply).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+
+        let error = move_todo(&backend, &args(&["inbox", "BUY", "nowhere"]), Mode::Apply);
+        assert!(matches!(error, Err(ThingyError::NotFound { .. })));
+        assert_eq!(names(&backend, "Inbox"), vec!["Buy milk", "Call mom"]);
+    }
+
+    #[test]
+    fn test_add_todo_to_builtin_list() {
+        let backend = FileBackend::in_memory();
+        add_todo(&backend, &args(&["someday", "Learn", "piano"]), None).unwrap();
+        add_todo(&backend, &args(&["Buy", "milk"]), None).unwrap();
+        assert_eq!(names(&backend, "Someday"), vec!["Learn piano"]);
+        assert_eq!(names(&backend, "Today"), vec!["Buy milk"]);
+    }
+
+    #[test]
+    fn test_add_todo_to_project_needs_exact_name() {
+        let backend = FileBackend::in_memory();
+        backend.create_project("Website relaunch", None).unwrap();
+        add_todo(&backend, &args(&["website relaunch", "Fix", "footer"]), None).unwrap();
+        add_todo(&backend, &args(&["website", "redesign"]), None).unwrap();
+        assert_eq!(names(&backend, "Website relaunch"), vec!["Fix footer"]);
+        assert_eq!(names(&backend, "Today"), vec!["website redesign"]);
+    }
+
+    #[test]
+    fn test_move_todo_to_project_by_prefix() {
+        let backend = backend_with("Anytime", &["Fix footer"]);
+        backend.create_project("Website relaunch", Some("Work")).unwrap();
+        move_todo(&backend, &args(&["anytime", "FIX", "website relaunch"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Website relaunch"), vec!["Fix footer"]);
+    }
+
+    #[test]
+    fn test_complete_todo_in_someday() {
+        let backend = backend_with("Someday", &["Learn piano", "Read book"]);
+        complete_todo(&backend, &args(&["someday", "REA"]), Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Someday"), vec!["Learn piano"]);
+    }
+
+    #[test]
+    fn test_project_new_done_rm() {
+        let backend = FileBackend::in_memory();
+        new_project(&backend, "Website relaunch", Some("Work")).unwrap();
+        new_project(&backend, "Wedding", None).unwrap();
+        add_todo(&backend, &args(&["Website relaunch", "Fix", "footer"]), None).unwrap();
+        add_todo(&backend, &args(&["Wedding", "--heading", "Venue", "Call", "caterer"]), None).unwrap();
+
+        let projects = fetch_lists_of_kind(&backend, ListKind::Project).unwrap();
+        assert_eq!(projects[0].identifier, "WEB");
+        assert_eq!(projects[0].area.as_deref(), Some("Work"));
+        assert_eq!(names(&backend, "Wedding"), vec!["Call caterer"]);
+
+        complete_project(&backend, "web").unwrap();
+        assert_eq!(backend.fetch_completed("Website relaunch").unwrap().len(), 1);
+        remove_project(&backend, "WED").unwrap();
+        assert!(fetch_lists_of_kind(&backend, ListKind::Project).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_todo_quick_syntax() {
+        let backend = FileBackend::in_memory();
+        add_todo(&backend, &args(&["inbox", "Buy", "milk", "#errand", "!+3d", "--", "2%"]), None).unwrap();
+        let todo = &backend.fetch_todos("Inbox").unwrap()[0];
+        assert_eq!(todo.name, "Buy milk");
+        assert_eq!(todo.tags, vec!["errand"]);
+        assert!(todo.deadline.is_some());
+        assert_eq!(backend.fetch_notes(&todo.id).unwrap(), "2%");
+    }
+
+    #[test]
+    fn test_split_ids_and_tags() {
+        let todos = vec![
+            make_todo("Buy milk", "BUY", 1),
+            make_todo("Fun run", "FUN", 2),
+        ];
+        let (selected, tags) =
+            split_ids_and_tags(&todos, &args(&["buy", "2", "errand", "home"])).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(tags, vec!["errand", "home"]);
+
+        // The last argument is always a tag, and # forces one:
+        let (selected, tags) = split_ids_and_tags(&todos, &args(&["BUY", "fun"])).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(tags, vec!["fun"]);
+        let (selected, tags) = split_ids_and_tags(&todos, &args(&["BUY", "#fun", "x"])).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(tags, vec!["fun", "x"]);
+
+        assert!(split_ids_and_tags(&todos, &args(&["XYZ", "errand"])).is_err());
+        assert!(split_ids_and_tags(&todos, &args(&["BUY", "#"])).is_err());
+    }
+
+    #[test]
+    fn test_tag_and_untag() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom"]);
+        tag_todos(&backend, &args(&["BUY", "CAL", "errand", "phone"]), Mode::Apply).unwrap();
+        untag_todos(&backend, &args(&["CAL", "errand"]), Mode::Apply).unwrap();
+        let todos = backend.fetch_todos("Today").unwrap();
+        assert_eq!(todos[0].tags, vec!["errand", "phone"]);
+        assert_eq!(todos[1].tags, vec!["phone"]);
+    }
+
+    #[test]
+    fn test_undo_remove_restores_from_trash() {
+        let backend = backend_with("Inbox", &["Buy milk", "Call mom"]);
+        remove_todo(&backend, &args(&["inbox", "BUY", "CAL"]), Mode::Apply).unwrap();
+        assert!(names(&backend, "Inbox").is_empty());
+
+        undo_command(&backend, 1, Mode::Apply).unwrap();
+        assert_eq!(names(&backend, "Inbox"), vec!["Buy milk", "Call mom"]);
+        assert!(backend.journal().entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_several_changes() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom"]);
+        workon_todo(&backend, &args(&["BUY"]), Mode::Apply).unwrap();
+        complete_todo(&backend, &args(&["BUY"]), Mode::Apply).unwrap();
+        move_todo(&backend, &args(&["today", "CAL", "someday"]), Mode::Apply).unwrap();
+
+        undo_command(&backend, 2, Mode::Apply).unwrap();
+        let todos = backend.fetch_todos("Today").unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].tags, vec!["in-progress"]);
+        assert_eq!(backend.journal().entries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_undo_rand() {
+        let backend = backend_with("Today", &["Buy milk"]);
+        rand_todo(&backend).unwrap();
+        assert_eq!(backend.fetch_todos("Today").unwrap()[0].tags, vec!["in-progress"]);
+
+        undo_command(&backend, 1, Mode::Apply).unwrap();
+        assert!(backend.fetch_todos("Today").unwrap()[0].tags.is_empty());
     }
 
     #[test]
-    fn test_parse_time_seconds_days() {
-        assert_eq!(parse_time_seconds("1d"), Some(86400));
-        assert_eq!(parse_time_seconds("2days"), Some(172800));
+    fn test_dry_run_changes_nothing() {
+        let backend = backend_with("Today", &["Buy milk", "Call mom"]);
+        complete_todo(&backend, &args(&["BUY"]), Mode::DryRun).unwrap();
+        remove_todo(&backend, &args(&["CAL"]), Mode::ShowScript).unwrap();
+        tag_todos(&backend, &args(&["BUY", "errand"]), Mode::DryRun).unwrap();
+        assert_eq!(names(&backend, "Today"), vec!["Buy milk", "Call mom"]);
+        assert!(backend.fetch_todos("Today").unwrap()[0].tags.is_empty());
+        assert!(backend.journal().entries().unwrap().is_empty());
+
+        remove_todo(&backend, &args(&["CAL"]), Mode::Apply).unwrap();
+        undo_command(&backend, 1, Mode::DryRun).unwrap();
+        assert_eq!(names(&backend, "Today"), vec!["Buy milk"]);
+        assert_eq!(backend.journal().entries().unwrap().len(), 1);
     }
 
     #[test]
-    fn test_parse_time_seconds_seconds() {
-        assert_eq!(parse_time_seconds("30s"), Some(30));
-        assert_eq!(parse_time_seconds("10sec"), Some(10));
-        assert_eq!(parse_time_seconds("60seconds"), Some(60));
+    fn test_describe_op() {
+        let id = "x".to_string();
+        let tags = Op::EditTags {
+            id: id.clone(),
+            add: vec!["errand".to_string()],
+            remove: vec!["in-progress".to_string()],
+        };
+        assert_eq!(describe_op(&tags, "BUY Buy milk"), "tag BUY Buy milk +errand -in-progress");
+        let move_op = Op::Move { id, to_list: "Someday".to_string() };
+        assert_eq!(describe_op(&move_op, "BUY Buy milk"), "move BUY Buy milk to Someday");
     }
 
     #[test]
-    fn test_parse_time_seconds_invalid() {
-        assert_eq!(parse_time_seconds(""), None);
-        assert_eq!(parse_time_seconds("abc"), None);
-        assert_eq!(parse_time_seconds("5x"), None);
-        assert_eq!(parse_time_seconds("  "), None);
+    fn test_take_option() {
+        let mut a = args(&["today", "--where", "tag:x", "--sort=name"]);
+        assert_eq!(take_option(&mut a, "--where").unwrap().as_deref(), Some("tag:x"));
+        assert_eq!(take_option(&mut a, "--sort").unwrap().as_deref(), Some("name"));
+        assert_eq!(take_option(&mut a, "--heading").unwrap(), None);
+        assert_eq!(a, args(&["today"]));
     }
 }
@@ -0,0 +1,408 @@
+// Shell completion.  The scripts `thingy completions` prints for bash,
+// zsh and fish hand the words typed so far to the hidden command
+//
+//     thingy __complete <words...> <partial>
+//
+// which prints one candidate per line, as the value and a description
+// separated by a tab.  Todos and lists come from the cache, kept for
+// at least five minutes, so a tab press rarely waits on Things3.
+
+use clap::Command;
+
+use crate::backend::Backend;
+use crate::commands::{fetch_todos_in_lists, visible_lists};
+use crate::config;
+use crate::lists::{assign_list_identifiers, ListInfo, ListKind, BUILT_IN_LISTS};
+use crate::todo::Todo;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub value: String,
+    pub description: String,
+}
+
+impl Candidate {
+    fn new(value: impl Into<String>, description: impl Into<String>) -> Self {
+        Candidate {
+            value: value.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// Commands whose arguments are `[list] <id...>`.
+const TODO_COMMANDS: &[&str] = &["rm", "complete", "workon", "defer", "next", "show"];
+
+/// Candidates for the last of `words`, the command line after `thingy`,
+/// given the command-line grammar `cli`.
+pub fn complete(
+    mut cli: Command,
+    backend: &dyn Backend,
+    words: &[String],
+) -> Vec<Candidate> {
+    let Some((partial, before)) = words.split_last() else {
+        return Vec::new();
+    };
+
+    // Building propagates global options down to the subcommands:
+    cli.build();
+
+    // Find the subcommand being completed and its arguments so far:
+    let mut command = &cli;
+    let mut path: Vec<&str> = Vec::new();
+    let mut args: Vec<&str> = Vec::new();
+    // An option still waiting for its value:
+    let mut option: Option<&str> = None;
+    for word in before {
+        if option.take().is_some() {
+            continue;
+        }
+        if let Some(long) = word.strip_prefix("--") {
+            if !long.contains('=') && takes_value(command, long) {
+                option = Some(long);
+            }
+            continue;
+        }
+        if word.starts_with('-') && word.len() > 1 {
+            continue;
+        }
+        match command.find_subcommand(word.as_str()) {
+            Some(sub) if args.is_empty() => {
+                command = sub;
+                path.push(sub.get_name());
+            }
+            _ => args.push(word),
+        }
+    }
+
+    let candidates = if let Some(long) = option {
+        option_values(command, long, backend)
+    } else if partial.starts_with('-') {
+        options(command)
+    } else if args.is_empty() && command.has_subcommands() {
+        let mut candidates = subcommands(command);
+        if path.is_empty() {
+            candidates.extend(views());
+        }
+        candidates
+    } else {
+        arguments(command, &path, &args, backend)
+    };
+    candidates
+        .into_iter()
+        .filter(|c| c.value.to_lowercase().starts_with(&partial.to_lowercase()))
+        .map(|c| Candidate {
+            value: match_case(&c.value, partial),
+            ..c
+        })
+        .collect()
+}
+
+/// Whether `command`'s option `--long` takes a value.
+fn takes_value(command: &Command, long: &str) -> bool {
+    command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long))
+        .is_some_and(|arg| arg.get_action().takes_values())
+}
+
+/// `value`, if an identifier, in lowercase if that's how `partial` was
+/// typed; identifiers are case-insensitive.  Names keep their case.
+fn match_case(value: &str, partial: &str) -> String {
+    let is_identifier = value.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if is_identifier
+        && partial.chars().any(|c| c.is_alphabetic())
+        && !partial.chars().any(|c| c.is_uppercase())
+    {
+        value.to_lowercase()
+    } else {
+        value.to_string()
+    }
+}
+
+fn options(command: &Command) -> Vec<Candidate> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| {
+            let long = arg.get_long()?;
+            let help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+            Some(Candidate::new(format!("--{}", long), help))
+        })
+        .collect()
+}
+
+fn subcommands(command: &Command) -> Vec<Candidate> {
+    command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+        .map(|sub| {
+            let about = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+            Candidate::new(sub.get_name(), about)
+        })
+        .collect()
+}
+
+fn views() -> Vec<Candidate> {
+    config::get()
+        .views
+        .iter()
+        .map(|(name, view)| Candidate::new(name, view.description.clone().unwrap_or_default()))
+        .collect()
+}
+
+fn option_values(
+    command: &Command,
+    long: &str,
+    backend: &dyn Backend,
+) -> Vec<Candidate> {
+    if long == "area" {
+        return named_lists(backend, ListKind::Area);
+    }
+    command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long))
+        .map(|arg| {
+            arg.get_possible_values()
+                .iter()
+                .map(|value| Candidate::new(value.get_name(), ""))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Candidates for a positional argument, after `args`.
+fn arguments(
+    command: &Command,
+    path: &[&str],
+    args: &[&str],
+    backend: &dyn Backend,
+) -> Vec<Candidate> {
+    match path {
+        [name] if TODO_COMMANDS.contains(name) => todo_targets(args, backend),
+        ["tag" | "untag"] => {
+            let mut candidates = todo_targets(args, backend);
+            if !args.is_empty() {
+                candidates.extend(tags(&todos_in(&visible_lists(), backend)));
+            }
+            candidates
+        }
+        ["mv"] => {
+            let from = args.first().and_then(|first| find_list_name(first, backend));
+            let from = from.map_or_else(visible_lists, |from| vec![from]);
+            let mut candidates = identifiers(&todos_in(&from, backend));
+            candidates.extend(lists(backend));
+            candidates
+        }
+        ["ls" | "add"] if args.is_empty() => lists(backend),
+        ["project", "show" | "done" | "rm"] if args.is_empty() => {
+            named_lists(backend, ListKind::Project)
+        }
+        ["area", "show"] if args.is_empty() => named_lists(backend, ListKind::Area),
+        _ => command
+            .get_positionals()
+            .nth(args.len())
+            .map(|arg| {
+                arg.get_possible_values()
+                    .iter()
+                    .map(|value| Candidate::new(value.get_name(), ""))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// For `[list] <id...>`: identifiers, and lists if none has been given
+/// yet.
+fn todo_targets(args: &[&str], backend: &dyn Backend) -> Vec<Candidate> {
+    let list = args.first().and_then(|first| find_list_name(first, backend));
+    match list {
+        Some(list) => identifiers(&todos_in(&[list], backend)),
+        None => {
+            let mut candidates = identifiers(&todos_in(&visible_lists(), backend));
+            if args.is_empty() {
+                candidates.extend(lists(backend));
+            }
+            candidates
+        }
+    }
+}
+
+/// Open todos in `lists`, with the identifiers commands will resolve.
+fn todos_in(lists: &[String], backend: &dyn Backend) -> Vec<Todo> {
+    fetch_todos_in_lists(backend, lists).unwrap_or_default()
+}
+
+fn identifiers(todos: &[Todo]) -> Vec<Candidate> {
+    todos
+        .iter()
+        .map(|todo| Candidate::new(&todo.identifier, &todo.name))
+        .collect()
+}
+
+fn tags(todos: &[Todo]) -> Vec<Candidate> {
+    let mut tags: Vec<&str> = todos.iter().flat_map(|t| t.tags.iter().map(|s| s.as_str())).collect();
+    tags.sort();
+    tags.dedup();
+    tags.into_iter().map(|tag| Candidate::new(tag, "tag")).collect()
+}
+
+/// Built-in lists, areas and projects, by name.
+fn lists(backend: &dyn Backend) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> =
+        BUILT_IN_LISTS.iter().map(|name| Candidate::new(*name, "list")).collect();
+    for list in backend.fetch_lists().unwrap_or_default() {
+        candidates.push(Candidate::new(&list.name, list_description(&list)));
+    }
+    candidates
+}
+
+/// Areas or projects, by identifier.
+fn named_lists(backend: &dyn Backend, kind: ListKind) -> Vec<Candidate> {
+    let mut lists: Vec<ListInfo> = backend
+        .fetch_lists()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|l| l.kind == kind)
+        .collect();
+    assign_list_identifiers(&mut lists);
+    lists
+        .iter()
+        .map(|list| Candidate::new(&list.identifier, &list.name))
+        .collect()
+}
+
+fn list_description(list: &ListInfo) -> String {
+    match (list.kind, &list.area) {
+        (ListKind::Area, _) => "area".to_string(),
+        (ListKind::Project, Some(area)) => format!("project in {}", area),
+        _ => "project".to_string(),
+    }
+}
+
+/// The list `name` names exactly (any case), if any.
+fn find_list_name(name: &str, backend: &dyn Backend) -> Option<String> {
+    let wanted = name.to_lowercase();
+    BUILT_IN_LISTS
+        .iter()
+        .map(|l| l.to_string())
+        .chain(backend.fetch_lists().unwrap_or_default().into_iter().map(|l| l.name))
+        .find(|l| l.to_lowercase() == wanted)
+}
+
+/// The completion script for `shell`, if it's one that can use
+/// `thingy __complete`.
+pub fn script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH_SCRIPT),
+        "zsh" => Some(ZSH_SCRIPT),
+        "fish" => Some(FISH_SCRIPT),
+        _ => None,
+    }
+}
+
+const BASH_SCRIPT: &str = r#"_thingy() {
+    local value
+    COMPREPLY=()
+    while IFS=$'\t' read -r value _; do
+        COMPREPLY+=("$(printf '%q' "$value")")
+    done < <(thingy __complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null)
+}
+complete -F _thingy thingy
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef thingy
+
+_thingy() {
+    local line
+    local -a candidates
+    for line in "${(@f)$(thingy __complete "${(@)words[2,CURRENT]}" 2>/dev/null)}"; do
+        [[ -n $line ]] && candidates+=("${${line%%$'\t'*}//:/\\:}:${line#*$'\t'}")
+    done
+    _describe thingy candidates
+}
+
+if [[ $zsh_eval_context[-1] == loadautofunc ]]; then
+    _thingy "$@"
+else
+    compdef _thingy thingy
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"function __thingy_complete
+    set -l words (commandline -opc) (commandline -ct)
+    thingy __complete $words[2..-1] 2>/dev/null
+end
+complete -c thingy -f -a '(__thingy_complete)'
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Backend;
+    use crate::file_backend::FileBackend;
+    use crate::todo::NewTodo;
+
+    fn backend() -> FileBackend {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("Buy milk")).unwrap();
+        backend.add_todo("Inbox", &NewTodo::new("Call mom")).unwrap();
+        backend.add_todo("Someday", &NewTodo::new("Read book")).unwrap();
+        backend.create_project("Website relaunch", Some("Work")).unwrap();
+        backend
+    }
+
+    fn values(words: &[&str]) -> Vec<String> {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        complete(crate::cli::command(), &backend(), &words)
+            .into_iter()
+            .map(|c| c.value)
+            .collect()
+    }
+
+    #[test]
+    fn test_commands_and_options() {
+        assert_eq!(values(&["li"]), vec!["lists"]);
+        assert!(values(&[""]).contains(&"complete".to_string()));
+        assert!(!values(&[""]).contains(&"__complete".to_string()));
+        assert_eq!(values(&["rm", "--dry"]), vec!["--dry-run"]);
+        assert_eq!(values(&["--format", "t"]), vec!["text", "tsv"]);
+        assert_eq!(values(&["project", "d"]), vec!["done"]);
+    }
+
+    #[test]
+    fn test_identifiers_for_context() {
+        assert_eq!(values(&["done", "b"]), vec!["buy"]);
+        assert_eq!(values(&["done", "C"]), vec!["CAL"]);
+        assert_eq!(values(&["rm", "someday", ""]), vec!["REA"]);
+        assert!(values(&["done", ""]).contains(&"Someday".to_string()));
+        assert!(!values(&["done", "BUY", ""]).contains(&"Someday".to_string()));
+        assert_eq!(values(&["mv", ""])[..2], ["BUY", "CAL"]);
+        assert_eq!(values(&["mv", "someday", ""])[0], "REA");
+        assert!(values(&["mv", "BUY", ""]).contains(&"CAL".to_string()));
+        assert_eq!(values(&["ls", "web"]), vec!["Website relaunch"]);
+        assert_eq!(values(&["ls", "tod"]), vec!["Today"]);
+        assert_eq!(values(&["project", "show", ""]), vec!["WEB"]);
+        assert_eq!(values(&["project", "new", "--area", ""]), vec!["WOR"]);
+    }
+
+    #[test]
+    fn test_identifiers_match_commands() {
+        let backend = backend();
+        backend.add_todo("Inbox", &NewTodo::new("Buy bread")).unwrap();
+        let words = vec!["done".to_string(), "BU".to_string()];
+        let mut values: Vec<String> = complete(crate::cli::command(), &backend, &words)
+            .into_iter()
+            .map(|c| c.value)
+            .collect();
+        values.sort();
+        assert_eq!(values, vec!["BU1", "BUY"]);
+    }
+
+    #[test]
+    fn test_descriptions() {
+        let words = vec!["done".to_string(), "BU".to_string()];
+        let candidates = complete(crate::cli::command(), &backend(), &words);
+        assert_eq!(candidates, vec![Candidate::new("BUY", "Buy milk")]);
+    }
+}
@@ -0,0 +1,501 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::cli;
+use crate::lists::BUILT_IN_LISTS;
+use crate::query::{self, Expr, Op, SortKey};
+use crate::todo::parse_time_seconds;
+
+/// Settings from `~/.config/thingy/config.toml`, with `THINGY_*`
+/// environment variables taking precedence.
+///
+/// ```toml
+/// default_list = "Inbox"
+/// identifier_length = 4
+/// cache_ttl = "1m"
+///
+/// [tags]
+/// in_progress = "wip"
+/// on_deck = "up-next"
+///
+/// [things]
+/// reads = "database"
+/// writes = "url"
+/// auth_token = "..."
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// List used when a command isn't given one.
+    pub default_list: String,
+    /// Letters in a todo's identifier.
+    pub identifier_length: usize,
+    /// How long fetched Things3 data is reused (e.g. "30s"; "0s" to
+    /// always fetch).
+    pub cache_ttl: String,
+    pub tags: WorkflowTags,
+    pub things: ThingsConfig,
+    pub views: BTreeMap<String, ViewConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            default_list: "Today".to_string(),
+            identifier_length: 3,
+            cache_ttl: "30s".to_string(),
+            tags: WorkflowTags::default(),
+            things: ThingsConfig::default(),
+            views: BTreeMap::new(),
+        }
+    }
+}
+
+/// Tags `workon`, `next` and interactive mode put on todos.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkflowTags {
+    pub in_progress: String,
+    pub on_deck: String,
+}
+
+impl Default for WorkflowTags {
+    fn default() -> Self {
+        WorkflowTags {
+            in_progress: "in-progress".to_string(),
+            on_deck: "on-deck".to_string(),
+        }
+    }
+}
+
+/// How thingy talks to Things3 (see `backend::from_env`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThingsConfig {
+    pub reads: ReadSource,
+    /// Path of Things3's `main.sqlite`; looked up in Things3's group
+    /// container if empty.
+    pub database: String,
+    pub writes: WriteMethod,
+    /// Token from Things3's Settings > General > Enable Things URLs,
+    /// needed for `writes = "url"`.
+    pub auth_token: String,
+}
+
+/// Where todos, lists and tags are read from.  Changes go where `writes`
+/// says.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadSource {
+    #[default]
+    AppleScript,
+    /// Things3's SQLite database, read-only: much faster, and works while
+    /// Things3 isn't running.
+    Database,
+}
+
+/// How changes are made.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteMethod {
+    #[default]
+    AppleScript,
+    /// Things3's `things:///` URL scheme where it can, which is faster;
+    /// AppleScript for the rest.
+    Url,
+}
+
+const MAX_IDENTIFIER_LENGTH: usize = 8;
+
+/// A saved listing, run as `thingy <name>`.  Every condition given must
+/// hold.
+///
+/// ```toml
+/// [views.quick]
+/// description = "Errands I can knock out fast"
+/// tags = ["errand"]
+/// max_time = "15m"
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViewConfig {
+    pub description: Option<String>,
+    /// List, area or project to show; defaults to the default list.
+    pub list: Option<String>,
+    /// Tags a todo must all have.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tags a todo must not have.
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+    /// Longest time estimate (e.g. "15m"); implies a time tag.
+    pub max_time: Option<String>,
+    /// Only todos without tags.
+    #[serde(default)]
+    pub untagged: bool,
+    /// A `ls --where` query.
+    #[serde(rename = "where")]
+    pub query: Option<String>,
+    /// `ls --sort` keys.
+    pub sort: Option<String>,
+}
+
+impl ViewConfig {
+    /// The view's conditions as one query, or None if it has none.
+    pub fn filter(&self, today: NaiveDate) -> Result<Option<Expr>, String> {
+        let mut conditions = Vec::new();
+        for tag in &self.tags {
+            conditions.push(Expr::Tag(tag.clone()));
+        }
+        for tag in &self.exclude_tags {
+            conditions.push(Expr::Not(Box::new(Expr::Tag(tag.clone()))));
+        }
+        if let Some(max_time) = &self.max_time {
+            let secs = parse_time_seconds(max_time)
+                .ok_or_else(|| format!("Invalid max_time '{}' (e.g. 15m, 1h)", max_time))?;
+            conditions.push(Expr::Time(Op::Le, secs));
+        }
+        if self.untagged {
+            conditions.push(Expr::Not(Box::new(Expr::Has(query::Presence::Tags))));
+        }
+        if let Some(q) = &self.query {
+            conditions.push(query::parse(q, today)?);
+        }
+
+        Ok(conditions
+            .into_iter()
+            .reduce(|a, b| Expr::And(Box::new(a), Box::new(b))))
+    }
+
+    pub fn sort_keys(&self) -> Result<Option<Vec<SortKey>>, String> {
+        self.sort.as_deref().map(query::parse_sort).transpose()
+    }
+}
+
+/// `$THINGY_CONFIG`, else `$XDG_CONFIG_HOME/thingy/config.toml`, else
+/// `~/.config/thingy/config.toml`.
+pub fn config_path() -> PathBuf {
+    if let Ok(path) = env::var("THINGY_CONFIG") {
+        return PathBuf::from(path);
+    }
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string())).join(".config"),
+    };
+    config_dir.join("thingy").join("config.toml")
+}
+
+pub fn parse(contents: &str) -> Result<Config, String> {
+    let mut config: Config = toml::from_str(contents).map_err(|e| e.to_string())?;
+    config.validate()?;
+    Ok(config)
+}
+
+impl Config {
+    /// Seconds fetched data may be reused for (see `cache`).
+    pub fn cache_ttl_secs(&self) -> u64 {
+        parse_time_seconds(&self.cache_ttl).unwrap_or(0)
+    }
+
+    /// Applies `THINGY_IN_PROGRESS_TAG`, `THINGY_ON_DECK_TAG`,
+    /// `THINGY_DEFAULT_LIST`, `THINGY_IDENTIFIER_LENGTH`,
+    /// `THINGY_CACHE_TTL`, `THINGY_THINGS_READS`, `THINGY_THINGS_DATABASE`,
+    /// `THINGY_THINGS_WRITES` and `THINGY_THINGS_AUTH_TOKEN`, looked up
+    /// with `var`.
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        if let Some(tag) = var("THINGY_IN_PROGRESS_TAG") {
+            self.tags.in_progress = tag;
+        }
+        if let Some(tag) = var("THINGY_ON_DECK_TAG") {
+            self.tags.on_deck = tag;
+        }
+        if let Some(list) = var("THINGY_DEFAULT_LIST") {
+            self.default_list = list;
+        }
+        if let Some(length) = var("THINGY_IDENTIFIER_LENGTH") {
+            self.identifier_length = length
+                .trim()
+                .parse()
+                .map_err(|_| format!("THINGY_IDENTIFIER_LENGTH must be a number, not '{}'", length))?;
+        }
+        if let Some(ttl) = var("THINGY_CACHE_TTL") {
+            self.cache_ttl = ttl;
+        }
+        if let Some(reads) = var("THINGY_THINGS_READS") {
+            self.things.reads = match reads.trim().to_lowercase().as_str() {
+                "applescript" => ReadSource::AppleScript,
+                "database" => ReadSource::Database,
+                _ => {
+                    return Err(format!(
+                        "THINGY_THINGS_READS must be applescript or database, not '{}'",
+                        reads
+                    ))
+                }
+            };
+        }
+        if let Some(path) = var("THINGY_THINGS_DATABASE") {
+            self.things.database = path;
+        }
+        if let Some(writes) = var("THINGY_THINGS_WRITES") {
+            self.things.writes = match writes.trim().to_lowercase().as_str() {
+                "applescript" => WriteMethod::AppleScript,
+                "url" => WriteMethod::Url,
+                _ => {
+                    return Err(format!(
+                        "THINGY_THINGS_WRITES must be applescript or url, not '{}'",
+                        writes
+                    ))
+                }
+            };
+        }
+        if let Some(token) = var("THINGY_THINGS_AUTH_TOKEN") {
+            self.things.auth_token = token;
+        }
+        self.validate()
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        if !(1..=MAX_IDENTIFIER_LENGTH).contains(&self.identifier_length) {
+            return Err(format!(
+                "identifier_length must be between 1 and {}",
+                MAX_IDENTIFIER_LENGTH
+            ));
+        }
+        for (key, tag) in [("in_progress", &self.tags.in_progress), ("on_deck", &self.tags.on_deck)] {
+            if tag.trim().is_empty() || tag.contains(',') {
+                return Err(format!("tags.{} must be a single tag name", key));
+            }
+        }
+        if parse_time_seconds(&self.cache_ttl).is_none() {
+            return Err(format!("cache_ttl must be a time such as \"30s\", not '{}'", self.cache_ttl));
+        }
+        if self.things.writes == WriteMethod::Url && self.things.auth_token.trim().is_empty() {
+            return Err("things.auth_token is needed for writes = \"url\"".to_string());
+        }
+        if self.default_list.trim().is_empty() {
+            return Err("default_list can't be empty".to_string());
+        }
+        if let Some(name) = self.views.keys().find(|name| cli::is_command(name)) {
+            return Err(format!("view '{}' is named like a command, which would hide it", name));
+        }
+        // Built-in lists are matched case-insensitively everywhere else:
+        if let Some(name) = BUILT_IN_LISTS
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(self.default_list.trim()))
+        {
+            self.default_list = name.to_string();
+        }
+        Ok(())
+    }
+}
+
+/// Loads the config file and applies environment overrides; a missing
+/// file means the defaults.
+pub fn load() -> Result<Config, String> {
+    let path = config_path();
+    let mut config = match fs::read_to_string(&path) {
+        Ok(contents) => parse(&contents).map_err(|e| format!("Error in {}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+        Err(e) => return Err(format!("Error reading {}: {}", path.display(), e)),
+    };
+    config.apply_env(|name| env::var(name).ok())?;
+    Ok(config)
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Loads the config for `get`.  Call once, before anything uses it.
+pub fn init() -> Result<(), String> {
+    let config = load()?;
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// The config `init` loaded, or the defaults if it wasn't called (as in
+/// tests, whatever the user running them has configured).
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::{parse_tag_names, Todo};
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()
+    }
+
+    fn todo(tags: &str) -> Todo {
+        Todo {
+            name: "Something".to_string(),
+            tags: parse_tag_names(tags),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_views() {
+        let config = parse(
+            r#"
+[views.quick]
+max_time = "15m"
+tags = ["errand"]
+
+[views.someday-reading]
+list = "Someday"
+where = "name:read"
+sort = "-created"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.views.len(), 2);
+        assert_eq!(config.views["quick"].tags, vec!["errand"]);
+        assert_eq!(config.views["someday-reading"].list.as_deref(), Some("Someday"));
+        assert!(config.views["someday-reading"].sort_keys().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_empty_config() {
+        assert!(parse("").unwrap().views.is_empty());
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let config = parse(
+            r#"
+default_list = "inbox"
+identifier_length = 4
+cache_ttl = "2m"
+
+[tags]
+in_progress = "wip"
+
+[things]
+reads = "database"
+writes = "url"
+auth_token = "abc"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.default_list, "Inbox");
+        assert_eq!(config.identifier_length, 4);
+        assert_eq!(config.things.reads, ReadSource::Database);
+        assert_eq!(config.cache_ttl_secs(), 120);
+        assert_eq!(config.tags.in_progress, "wip");
+        assert_eq!(config.tags.on_deck, "on-deck");
+        assert_eq!(config.things.writes, WriteMethod::Url);
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = parse("").unwrap();
+        assert_eq!(config.default_list, "Today");
+        assert_eq!(config.identifier_length, 3);
+        assert_eq!(config.cache_ttl_secs(), 30);
+        assert_eq!(config.tags.in_progress, "in-progress");
+        assert_eq!(config.things.reads, ReadSource::AppleScript);
+        assert_eq!(config.things.writes, WriteMethod::AppleScript);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(parse("identifier_length = 0").is_err());
+        assert!(parse("identifier_length = 20").is_err());
+        assert!(parse("[tags]\non_deck = \"\"").is_err());
+        assert!(parse("[tags]\non_deck = \"a, b\"").is_err());
+        assert!(parse("default_list = \" \"").is_err());
+        assert!(parse("cache_ttl = \"soon\"").is_err());
+        assert!(parse("[things]\nreads = \"magic\"").is_err());
+        assert!(parse("[things]\nwrites = \"url\"").is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut config = parse("default_list = \"Inbox\"\n[tags]\nin_progress = \"wip\"").unwrap();
+        config
+            .apply_env(|name| match name {
+                "THINGY_DEFAULT_LIST" => Some("someday".to_string()),
+                "THINGY_ON_DECK_TAG" => Some("up-next".to_string()),
+                "THINGY_IDENTIFIER_LENGTH" => Some("4".to_string()),
+                "THINGY_THINGS_READS" => Some("Database".to_string()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(config.default_list, "Someday");
+        assert_eq!(config.tags.in_progress, "wip");
+        assert_eq!(config.tags.on_deck, "up-next");
+        assert_eq!(config.identifier_length, 4);
+
+        let bad_length = |name: &str| (name == "THINGY_IDENTIFIER_LENGTH").then(|| "four".to_string());
+        assert!(Config::default().apply_env(bad_length).is_err());
+    }
+
+    #[test]
+    fn test_views_named_like_commands_are_errors() {
+        for name in ["today", "done", "ls", "help"] {
+            let error = parse(&format!("[views.{}]\ntags = [\"errand\"]", name)).unwrap_err();
+            assert!(error.contains(&format!("view '{}'", name)), "{}", error);
+        }
+        assert!(parse("[views.Today]\ntags = [\"errand\"]").is_ok());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_errors() {
+        assert!(parse("[views.quick]\nmax_tme = \"15m\"").is_err());
+        assert!(parse("colour = \"red\"").is_err());
+    }
+
+    #[test]
+    fn test_view_filter() {
+        let view = ViewConfig {
+            tags: vec!["errand".to_string()],
+            exclude_tags: vec!["in-progress".to_string()],
+            max_time: Some("15m".to_string()),
+            ..Default::default()
+        };
+        let filter = view.filter(today()).unwrap().unwrap();
+
+        assert!(filter.matches(&todo("errand, 10m")));
+        assert!(!filter.matches(&todo("errand, 30m")));
+        assert!(!filter.matches(&todo("errand")));
+        assert!(!filter.matches(&todo("errand, 10m, in-progress")));
+        assert!(!filter.matches(&todo("10m")));
+    }
+
+    #[test]
+    fn test_view_filter_untagged_and_query() {
+        let view = ViewConfig {
+            untagged: true,
+            query: Some("name:some".to_string()),
+            ..Default::default()
+        };
+        let filter = view.filter(today()).unwrap().unwrap();
+        assert!(filter.matches(&todo("")));
+        assert!(!filter.matches(&todo("errand")));
+    }
+
+    #[test]
+    fn test_view_filter_errors() {
+        let view = ViewConfig {
+            max_time: Some("soon".to_string()),
+            ..Default::default()
+        };
+        assert!(view.filter(today()).is_err());
+        let view = ViewConfig {
+            max_time: Some("99999999999999999d".to_string()),
+            ..Default::default()
+        };
+        assert!(view.filter(today()).is_err());
+        assert!(ViewConfig::default().filter(today()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, ThingyError>;
+
+/// Everything that can go wrong running a command.  Each class exits
+/// with its own code (see `exit_code`), so scripts can tell them apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThingyError {
+    /// osascript or Things3 isn't there to talk to.
+    ThingsNotInstalled,
+    ThingsNotRunning,
+    /// macOS hasn't allowed us to control Things3.
+    PermissionDenied,
+    /// No todo, list, project or area by that name.
+    NotFound {
+        message: String,
+        hint: Option<String>,
+    },
+    /// An identifier matching several todos, given as "IDENT name".
+    AmbiguousId {
+        query: String,
+        candidates: Vec<String>,
+    },
+    /// Input, config or Things output we couldn't make sense of.
+    Parse(String),
+    /// An AppleScript error, with AppleScript's error number if known.
+    Script {
+        number: Option<i32>,
+        message: String,
+    },
+    /// A command used wrongly; `usage` shows how to use it.
+    Usage {
+        message: String,
+        usage: Option<String>,
+    },
+    /// Some changes of a batch failed (each already reported); `first`
+    /// is the first failure.
+    Failed {
+        failed: usize,
+        total: usize,
+        first: Box<ThingyError>,
+    },
+    Other(String),
+}
+
+impl ThingyError {
+    pub fn not_found(message: impl Into<String>, hint: impl Into<Option<String>>) -> Self {
+        ThingyError::NotFound {
+            message: message.into(),
+            hint: hint.into(),
+        }
+    }
+
+    pub fn usage(message: impl Into<String>, usage: impl Into<Option<String>>) -> Self {
+        ThingyError::Usage {
+            message: message.into(),
+            usage: usage.into(),
+        }
+    }
+
+    /// Classifies an AppleScript error by its number.
+    pub fn from_script(number: Option<i32>, message: &str) -> Self {
+        let message = message.trim().to_string();
+        match number {
+            Some(-1743) => ThingyError::PermissionDenied,
+            Some(-600) => ThingyError::ThingsNotRunning,
+            Some(-10814) | Some(-2740) => ThingyError::ThingsNotInstalled,
+            Some(-1728) | Some(-1719) => ThingyError::not_found(message, None),
+            _ if message.contains("Can't get application") => ThingyError::ThingsNotInstalled,
+            _ => ThingyError::Script { number, message },
+        }
+    }
+
+    /// Classifies osascript's error output, such as
+    /// `0:42: execution error: Things3 got an error: Can't get to do id "x". (-1728)`.
+    pub fn from_osascript(stderr: &str) -> Self {
+        let text = stderr.trim();
+        let text = match text.find("error: ") {
+            Some(pos) => &text[pos + "error: ".len()..],
+            None => text,
+        };
+        let (message, number) = match text.rfind(" (") {
+            Some(pos) if text.ends_with(')') => match text[pos + 2..text.len() - 1].parse() {
+                Ok(number) => (&text[..pos], Some(number)),
+                Err(_) => (text, None),
+            },
+            _ => (text, None),
+        };
+        ThingyError::from_script(number, message)
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ThingyError::Other(_) => 1,
+            ThingyError::Usage { .. } => 2,
+            ThingyError::NotFound { .. } => 3,
+            ThingyError::AmbiguousId { .. } => 4,
+            ThingyError::ThingsNotInstalled => 5,
+            ThingyError::ThingsNotRunning => 6,
+            ThingyError::PermissionDenied => 7,
+            ThingyError::Script { .. } => 8,
+            ThingyError::Parse(_) => 9,
+            ThingyError::Failed { first, .. } => first.exit_code(),
+        }
+    }
+
+    /// What to do about the error, if we can suggest something.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            ThingyError::ThingsNotInstalled => Some(
+                "thingy needs Things3 on macOS; elsewhere, set THINGY_BACKEND=file \
+                 to keep todos in a JSON file"
+                    .to_string(),
+            ),
+            ThingyError::ThingsNotRunning => Some("Open Things3 and try again".to_string()),
+            ThingyError::PermissionDenied => Some(
+                "Allow your terminal to control Things3 in System Settings > \
+                 Privacy & Security > Automation"
+                    .to_string(),
+            ),
+            ThingyError::NotFound { hint, .. } => hint.clone(),
+            ThingyError::AmbiguousId { .. } => {
+                Some("Use one of the identifiers above".to_string())
+            }
+            ThingyError::Usage { usage, .. } => usage.as_ref().map(|u| format!("Usage: {}", u)),
+            ThingyError::Failed { first, .. } => first.hint(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ThingyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThingyError::ThingsNotInstalled => write!(f, "Things3 isn't installed"),
+            ThingyError::ThingsNotRunning => write!(f, "Things3 isn't running"),
+            ThingyError::PermissionDenied => write!(f, "Not allowed to control Things3"),
+            ThingyError::NotFound { message, .. } => write!(f, "{}", message),
+            ThingyError::AmbiguousId { query, candidates } => {
+                match candidates.len() {
+                    1 => write!(f, "'{}' only loosely matches:", query)?,
+                    n => write!(f, "'{}' matches {} todos:", query, n)?,
+                }
+                for (n, candidate) in candidates.iter().enumerate() {
+                    write!(f, "\n  {}. {}", n + 1, candidate)?;
+                }
+                Ok(())
+            }
+            ThingyError::Script {
+                number: Some(number),
+                message,
+            } => write!(f, "AppleScript error {}: {}", number, message),
+            ThingyError::Script { number: None, message } => {
+                write!(f, "AppleScript error: {}", message)
+            }
+            ThingyError::Failed { failed, total, .. } => {
+                write!(f, "{} of {} failed", failed, total)
+            }
+            ThingyError::Parse(message)
+            | ThingyError::Usage { message, .. }
+            | ThingyError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ThingyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_osascript() {
+        let error = ThingyError::from_osascript(
+            "0:42: execution error: Things3 got an error: Can't get to do id \"x\". (-1728)\n",
+        );
+        assert_eq!(
+            error,
+            ThingyError::not_found("Things3 got an error: Can't get to do id \"x\".", None)
+        );
+
+        let error = ThingyError::from_osascript(
+            "0:9: execution error: Not authorized to send Apple events to Things3. (-1743)",
+        );
+        assert_eq!(error, ThingyError::PermissionDenied);
+
+        let error = ThingyError::from_osascript("0:5: syntax error: Expected end of line. (-2741)");
+        assert_eq!(
+            error,
+            ThingyError::Script {
+                number: Some(-2741),
+                message: "Expected end of line.".to_string()
+            }
+        );
+
+        let error = ThingyError::from_osascript("something odd (really)");
+        assert!(matches!(error, ThingyError::Script { number: None, .. }));
+    }
+
+    #[test]
+    fn test_exit_codes_differ_by_class() {
+        let errors = [
+            ThingyError::Other("x".to_string()),
+            ThingyError::usage("x", None),
+            ThingyError::not_found("x", None),
+            ThingyError::AmbiguousId { query: "x".to_string(), candidates: Vec::new() },
+            ThingyError::ThingsNotInstalled,
+            ThingyError::ThingsNotRunning,
+            ThingyError::PermissionDenied,
+            ThingyError::Script { number: None, message: "x".to_string() },
+            ThingyError::Parse("x".to_string()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        codes.dedup();
+        assert_eq!(codes, (1..=9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_failed_batch_takes_first_error_class() {
+        let error = ThingyError::Failed {
+            failed: 2,
+            total: 3,
+            first: Box::new(ThingyError::ThingsNotRunning),
+        };
+        assert_eq!(error.to_string(), "2 of 3 failed");
+        assert_eq!(error.exit_code(), 6);
+        assert_eq!(error.hint().as_deref(), Some("Open Things3 and try again"));
+    }
+}
@@ -0,0 +1,761 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{self, Backend, Op};
+use crate::config;
+use crate::error::{Result, ThingyError};
+use crate::journal::{Journal, Snapshot};
+use crate::lists::{self, ListInfo, ListKind};
+use crate::registry::{write_replacing, RegistryStore};
+use crate::tags::TagInfo;
+use crate::todo::{NewTodo, Todo, When};
+
+/// A backend that keeps todos in a JSON file (or only in memory), for
+/// machines without Things3 and for tests.
+pub struct FileBackend {
+    path: Option<PathBuf>,
+    store: RefCell<Store>,
+    /// Set while applying a batch, which is saved once at the end.
+    in_batch: Cell<bool>,
+    journal: Journal,
+    registry: RegistryStore,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    next_id: u64,
+    #[serde(default)]
+    areas: Vec<String>,
+    #[serde(default)]
+    projects: Vec<StoredProject>,
+    #[serde(default)]
+    tags: Vec<StoredTag>,
+    todos: Vec<StoredTodo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredTag {
+    name: String,
+    #[serde(default)]
+    parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredProject {
+    #[serde(default)]
+    id: String,
+    name: String,
+    #[serde(default)]
+    area: Option<String>,
+    #[serde(default)]
+    completed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredTodo {
+    id: String,
+    name: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    list: String,
+    #[serde(default)]
+    heading: Option<String>,
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    created: Option<NaiveDate>,
+    #[serde(default)]
+    when: Option<NaiveDate>,
+    #[serde(default)]
+    deadline: Option<NaiveDate>,
+    #[serde(default)]
+    completed_on: Option<NaiveDate>,
+    #[serde(default)]
+    checklist: Vec<String>,
+}
+
+impl StoredTodo {
+    fn in_list(&self, list_name: &str, today: NaiveDate) -> bool {
+        if self.list.eq_ignore_ascii_case(list_name) {
+            return true;
+        }
+        // Deferred todos come back to Today once their date arrives:
+        list_name.eq_ignore_ascii_case("Today")
+            && self.list == "Upcoming"
+            && self.when.is_some_and(|d| d <= today)
+    }
+
+    fn to_todo(&self, index: usize, list_name: &str, projects: &[StoredProject]) -> Todo {
+        Todo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            tags: self.tags.clone(),
+            is_completed: self.completed,
+            index,
+            list: list_name.to_string(),
+            when: self.when,
+            deadline: self.deadline,
+            created: self.created,
+            completed_on: self.completed_on,
+            project: projects
+                .iter()
+                .find(|p| p.name == self.list)
+                .map(|p| p.name.clone()),
+            notes: self.notes.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+impl FileBackend {
+    /// Opens the store at `path`; a missing file is treated as empty.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let store = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                ThingyError::Parse(format!("Error reading {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Store::default(),
+            Err(e) => {
+                return Err(ThingyError::Other(format!(
+                    "Error reading {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(FileBackend {
+            path: Some(path),
+            store: RefCell::new(store),
+            in_batch: Cell::new(false),
+            journal: Journal::from_env(),
+            registry: RegistryStore::from_env(),
+        })
+    }
+
+    /// A backend that keeps todos, its journal and identifiers in memory
+    /// only.
+    pub fn in_memory() -> Self {
+        FileBackend {
+            path: None,
+            store: RefCell::new(Store::default()),
+            in_batch: Cell::new(false),
+            journal: Journal::in_memory(),
+            registry: RegistryStore::in_memory(),
+        }
+    }
+
+    fn collect_todos<'a>(
+        &self,
+        store: &Store,
+        todos: impl Iterator<Item = &'a StoredTodo>,
+        list_name: &str,
+    ) -> Vec<Todo> {
+        let mut todos: Vec<Todo> = todos
+            .enumerate()
+            .map(|(idx, t)| t.to_todo(idx + 1, list_name, &store.projects))
+            .collect();
+        crate::identifiers::assign_identifiers(&mut todos, &self.registry);
+        todos
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if self.in_batch.get() {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&*self.store.borrow())
+            .map_err(|e| ThingyError::Other(format!("Error serializing todos: {}", e)))?;
+        write_replacing(path, &json).map_err(|e| {
+            ThingyError::Other(format!("Error writing {}: {}", path.display(), e))
+        })
+    }
+
+    fn position(&self, id: &str) -> Result<usize> {
+        self.store
+            .borrow()
+            .todos
+            .iter()
+            .position(|t| t.id == id && t.list != "Trash")
+            .ok_or_else(|| ThingyError::not_found(format!("No todo with id {}", id), None))
+    }
+
+    fn next_id(store: &mut Store) -> String {
+        store.next_id += 1;
+        format!("local-{}", store.next_id)
+    }
+
+    fn project_position(&self, id: &str) -> Result<usize> {
+        self.store
+            .borrow()
+            .projects
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| ThingyError::not_found(format!("No project with id {}", id), None))
+    }
+
+    /// Applies `f` to the todo with `id`, saves, and returns the todo's name.
+    fn update<F>(&self, id: &str, f: F) -> Result<String>
+    where
+        F: FnOnce(&mut StoredTodo),
+    {
+        let pos = self.position(id)?;
+        let name = {
+            let mut store = self.store.borrow_mut();
+            let todo = &mut store.todos[pos];
+            f(todo);
+            todo.name.clone()
+        };
+        self.save()?;
+        Ok(name)
+    }
+}
+
+impl Backend for FileBackend {
+    fn fetch_todos(&self, list_name: &str) -> Result<Vec<Todo>> {
+        let today = today();
+        let store = self.store.borrow();
+        Ok(self.collect_todos(
+            &store,
+            store.todos.iter().filter(|t| t.in_list(list_name, today) && !t.completed),
+            list_name,
+        ))
+    }
+
+    fn fetch_completed(&self, list_name: &str) -> Result<Vec<Todo>> {
+        let today = today();
+        let store = self.store.borrow();
+        Ok(self.collect_todos(
+            &store,
+            store.todos.iter().filter(|t| t.in_list(list_name, today) && t.completed),
+            list_name,
+        ))
+    }
+
+    fn fetch_all(&self, list_name: &str) -> Result<Vec<Todo>> {
+        let today = today();
+        let store = self.store.borrow();
+        Ok(self.collect_todos(
+            &store,
+            store.todos.iter().filter(|t| t.in_list(list_name, today)),
+            list_name,
+        ))
+    }
+
+    fn fetch_logbook(&self, days: u32) -> Result<Vec<Todo>> {
+        let cutoff = today() - Days::new(days as u64);
+        let store = self.store.borrow();
+        let mut logged: Vec<&StoredTodo> = store
+            .todos
+            .iter()
+            .filter(|t| t.completed && t.list != "Trash")
+            .filter(|t| t.completed_on.is_some_and(|d| d > cutoff))
+            .collect();
+        logged.sort_by_key(|t| std::cmp::Reverse(t.completed_on));
+        Ok(self.collect_todos(&store, logged.into_iter(), "Logbook"))
+    }
+
+    fn fetch_notes(&self, id: &str) -> Result<String> {
+        let pos = self.position(id)?;
+        Ok(self.store.borrow().todos[pos].notes.clone())
+    }
+
+    fn fetch_lists(&self) -> Result<Vec<ListInfo>> {
+        let store = self.store.borrow();
+        // Areas are stored by name alone, which doubles as their id:
+        let areas = store.areas.iter().map(|name| ListInfo {
+            id: name.clone(),
+            name: name.clone(),
+            kind: ListKind::Area,
+            ..Default::default()
+        });
+        let projects = store.projects.iter().filter(|p| !p.completed).map(|p| ListInfo {
+            id: p.id.clone(),
+            name: p.name.clone(),
+            kind: ListKind::Project,
+            area: p.area.clone(),
+            ..Default::default()
+        });
+        Ok(areas.chain(projects).collect())
+    }
+
+    fn add_todo(&self, list_name: &str, todo: &NewTodo) -> Result<String> {
+        let today = today();
+        let built_in = lists::is_built_in(list_name);
+        // Scheduling a todo moves it between built-in lists, as in Things3:
+        let (list, when) = match todo.when {
+            Some(When::Date(date)) if built_in && date > today => ("Upcoming", Some(date)),
+            Some(When::Date(date)) if built_in => ("Today", Some(date)),
+            Some(When::Date(date)) => (list_name, Some(date)),
+            Some(When::Anytime) if built_in => ("Anytime", None),
+            Some(When::Someday) if built_in => ("Someday", None),
+            _ => (list_name, None),
+        };
+        {
+            let mut store = self.store.borrow_mut();
+            let id = Self::next_id(&mut store);
+            store.todos.push(StoredTodo {
+                id,
+                name: todo.name.clone(),
+                notes: todo.notes.clone(),
+                tags: todo.tags.clone(),
+                list: list.to_string(),
+                heading: todo.heading.clone(),
+                completed: false,
+                created: Some(today),
+                when,
+                deadline: todo.deadline,
+                completed_on: None,
+                checklist: todo.checklist.clone(),
+            });
+        }
+        self.save()?;
+        Ok(todo.name.clone())
+    }
+
+    /// Areas are only listed in the file, so an unknown `area` is created.
+    fn create_project(&self, name: &str, area: Option<&str>) -> Result<String> {
+        {
+            let mut store = self.store.borrow_mut();
+            if let Some(area) = area {
+                if !store.areas.iter().any(|a| a == area) {
+                    store.areas.push(area.to_string());
+                }
+            }
+            let id = Self::next_id(&mut store);
+            store.projects.push(StoredProject {
+                id,
+                name: name.to_string(),
+                area: area.map(|a| a.to_string()),
+                completed: false,
+            });
+        }
+        self.save()?;
+        Ok(name.to_string())
+    }
+
+    fn complete_project(&self, id: &str) -> Result<String> {
+        let pos = self.project_position(id)?;
+        let name = {
+            let mut store = self.store.borrow_mut();
+            store.projects[pos].completed = true;
+            let name = store.projects[pos].name.clone();
+            for todo in store.todos.iter_mut().filter(|t| t.list == name && !t.completed) {
+                todo.completed = true;
+                todo.completed_on = Some(today());
+            }
+            name
+        };
+        self.save()?;
+        Ok(name)
+    }
+
+    fn delete_project(&self, id: &str) -> Result<String> {
+        let pos = self.project_position(id)?;
+        let name = {
+            let mut store = self.store.borrow_mut();
+            let project = store.projects.remove(pos);
+            for todo in store.todos.iter_mut().filter(|t| t.list == project.name) {
+                todo.list = "Trash".to_string();
+            }
+            project.name
+        };
+        self.save()?;
+        Ok(name)
+    }
+
+    fn complete_todo(&self, id: &str) -> Result<String> {
+        self.update(id, |todo| {
+            todo.tags.retain(|t| *t != config::get().tags.in_progress);
+            todo.completed = true;
+            todo.completed_on = Some(today());
+        })
+    }
+
+    fn delete_todo(&self, id: &str) -> Result<String> {
+        self.update(id, |todo| todo.list = "Trash".to_string())
+    }
+
+    fn move_todo(&self, id: &str, to_list: &str) -> Result<String> {
+        self.update(id, |todo| {
+            todo.list = to_list.to_string();
+            todo.when = None;
+        })
+    }
+
+    fn edit_tags(&self, id: &str, add: &[&str], remove: &[&str]) -> Result<String> {
+        {
+            let mut store = self.store.borrow_mut();
+            for tag in add {
+                if !store.tags.iter().any(|t| t.name == *tag) {
+                    store.tags.push(StoredTag {
+                        name: tag.to_string(),
+                        parent: None,
+                    });
+                }
+            }
+        }
+        self.update(id, |todo| {
+            todo.tags.retain(|t| !remove.contains(&t.as_str()));
+            for tag in add {
+                if !todo.tags.iter().any(|t| t == tag) {
+                    todo.tags.push(tag.to_string());
+                }
+            }
+        })
+    }
+
+    fn fetch_tags(&self) -> Result<Vec<TagInfo>> {
+        let store = self.store.borrow();
+        let open: Vec<&StoredTodo> = store
+            .todos
+            .iter()
+            .filter(|t| !t.completed && t.list != "Trash" && t.list != "Logbook")
+            .collect();
+        let count = |name: &str| {
+            open.iter()
+                .filter(|t| t.tags.iter().any(|tag| tag == name))
+                .count()
+        };
+
+        // Tags set by hand-editing the file may not be listed:
+        let mut tags: Vec<TagInfo> = store
+            .tags
+            .iter()
+            .map(|t| TagInfo {
+                name: t.name.clone(),
+                parent: t.parent.clone(),
+                count: count(&t.name),
+            })
+            .collect();
+        for todo in &store.todos {
+            for tag in &todo.tags {
+                if !tags.iter().any(|t| t.name == *tag) {
+                    tags.push(TagInfo {
+                        name: tag.clone(),
+                        parent: None,
+                        count: count(tag),
+                    });
+                }
+            }
+        }
+        Ok(tags)
+    }
+
+    fn defer_todo(&self, id: &str) -> Result<String> {
+        self.update(id, |todo| {
+            todo.list = "Upcoming".to_string();
+            todo.when = Some(today() + Days::new(1));
+        })
+    }
+
+    fn set_completed(&self, id: &str, completed: bool) -> Result<()> {
+        self.update(id, |todo| {
+            if completed {
+                todo.tags.retain(|t| *t != config::get().tags.in_progress);
+                todo.completed_on = Some(today());
+            } else {
+                todo.completed_on = None;
+            }
+            todo.completed = completed;
+        })
+        .map(|_| ())
+    }
+
+    fn toggle_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        let mut tags = Vec::new();
+        self.update(id, |todo| {
+            if todo.tags.iter().any(|t| t == tag) {
+                todo.tags.retain(|t| t != tag);
+            } else {
+                todo.tags.push(tag.to_string());
+            }
+            tags = todo.tags.clone();
+        })?;
+        Ok(tags)
+    }
+
+    fn log_completed(&self) -> Result<()> {
+        for todo in self.store.borrow_mut().todos.iter_mut() {
+            if todo.completed && todo.list != "Trash" {
+                todo.list = "Logbook".to_string();
+            }
+        }
+        self.save()
+    }
+
+    fn restore_todo(&self, id: &str, before: &Snapshot) -> Result<String> {
+        // Unlike `update`, this finds todos in the Trash too:
+        let name = {
+            let mut store = self.store.borrow_mut();
+            let todo = store
+                .todos
+                .iter_mut()
+                .find(|t| t.id == id)
+                .ok_or_else(|| ThingyError::not_found(format!("No todo with id {}", id), None))?;
+            todo.list = before.list.clone();
+            todo.completed_on = match before.completed {
+                true => todo.completed_on.or(Some(today())),
+                false => None,
+            };
+            todo.completed = before.completed;
+            todo.tags = before.tags.clone();
+            todo.when = before.when;
+            todo.deadline = before.deadline;
+            todo.name.clone()
+        };
+        self.save()?;
+        Ok(name)
+    }
+
+    fn apply(&self, ops: &[Op]) -> Vec<Result<String>> {
+        self.in_batch.set(true);
+        let mut results: Vec<Result<String>> =
+            ops.iter().map(|op| backend::apply_one(self, op)).collect();
+        self.in_batch.set(false);
+
+        if let Err(error) = self.save() {
+            for result in results.iter_mut().filter(|r| r.is_ok()) {
+                *result = Err(error.clone());
+            }
+        }
+        results
+    }
+
+    fn journal(&self) -> Journal {
+        self.journal.clone()
+    }
+
+    fn registry(&self) -> RegistryStore {
+        self.registry.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// The store at `path`, keeping identifiers and the journal in
+    /// memory rather than the user's files.
+    fn open(path: &Path) -> FileBackend {
+        FileBackend {
+            journal: Journal::in_memory(),
+            registry: RegistryStore::in_memory(),
+            ..FileBackend::open(path.to_path_buf()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_add_and_fetch() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("Buy milk")).unwrap();
+        backend.add_todo("Inbox", &NewTodo::new("Call mom")).unwrap();
+
+        let today = backend.fetch_todos("Today").unwrap();
+        assert_eq!(today.len(), 1);
+        assert_eq!(today[0].name, "Buy milk");
+        assert_eq!(today[0].identifier, "BUY");
+        assert_eq!(backend.count_todos("Inbox").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_complete_by_id() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("First")).unwrap();
+        backend.add_todo("Today", &NewTodo::new("Second")).unwrap();
+        let first = backend.fetch_todos("Today").unwrap()[0].id.clone();
+        backend.complete_todo(&first).unwrap();
+
+        let second = backend.fetch_todos("Today").unwrap()[0].id.clone();
+        assert_eq!(backend.complete_todo(&second).unwrap(), "Second");
+        assert!(backend.fetch_todos("Today").unwrap().is_empty());
+        assert_eq!(backend.fetch_completed("Today").unwrap().len(), 2);
+        assert_eq!(backend.fetch_logbook(1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_toggle_tag() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("Write report")).unwrap();
+        let id = backend.fetch_todos("Today").unwrap()[0].id.clone();
+
+        assert_eq!(backend.toggle_tag(&id, "in-progress").unwrap(), vec!["in-progress"]);
+        assert!(backend.toggle_tag(&id, "in-progress").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_log_completed() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("Done already")).unwrap();
+        backend.add_todo("Today", &NewTodo::new("Done and binned")).unwrap();
+        let ids: Vec<String> = backend.fetch_todos("Today").unwrap().into_iter().map(|t| t.id).collect();
+        backend.set_completed(&ids[0], true).unwrap();
+        backend.set_completed(&ids[1], true).unwrap();
+        backend.delete_todo(&ids[1]).unwrap();
+        backend.log_completed().unwrap();
+
+        assert!(backend.fetch_all("Today").unwrap().is_empty());
+        let names: Vec<String> = backend.fetch_logbook(1).unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["Done already"]);
+        assert_eq!(backend.fetch_all("Trash").unwrap()[0].name, "Done and binned");
+    }
+
+    #[test]
+    fn test_unknown_id() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("Buy milk")).unwrap();
+        assert!(backend.delete_todo("local-99").is_err());
+    }
+
+    #[test]
+    fn test_ids_survive_list_changes() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("First")).unwrap();
+        backend.add_todo("Today", &NewTodo::new("Second")).unwrap();
+        let second = backend.fetch_todos("Today").unwrap()[1].id.clone();
+
+        let first = backend.fetch_todos("Today").unwrap()[0].id.clone();
+        backend.delete_todo(&first).unwrap();
+
+        assert_eq!(backend.complete_todo(&second).unwrap(), "Second");
+    }
+
+    #[test]
+    fn test_projects_and_areas() {
+        let backend = FileBackend::in_memory();
+        backend.create_project("Launch", Some("Work")).unwrap();
+        backend.add_todo("Launch", &NewTodo::new("Write copy")).unwrap();
+
+        let lists = backend.fetch_lists().unwrap();
+        assert_eq!(lists[0].kind, ListKind::Area);
+        assert_eq!(lists[1].name, "Launch");
+        assert_eq!(lists[1].area.as_deref(), Some("Work"));
+        let todo = &backend.fetch_todos("launch").unwrap()[0];
+        assert_eq!(todo.name, "Write copy");
+        assert_eq!(todo.project.as_deref(), Some("Launch"));
+    }
+
+    #[test]
+    fn test_complete_and_delete_project() {
+        let backend = FileBackend::in_memory();
+        backend.create_project("Launch", None).unwrap();
+        backend.create_project("Move house", None).unwrap();
+        backend.add_todo("Launch", &NewTodo::new("Write copy")).unwrap();
+        let boxes = NewTodo {
+            heading: Some("Packing".to_string()),
+            ..NewTodo::new("Buy boxes")
+        };
+        backend.add_todo("Move house", &boxes).unwrap();
+        let lists = backend.fetch_lists().unwrap();
+
+        assert_eq!(backend.complete_project(&lists[0].id).unwrap(), "Launch");
+        assert_eq!(backend.fetch_completed("Launch").unwrap()[0].name, "Write copy");
+        assert_eq!(backend.delete_project(&lists[1].id).unwrap(), "Move house");
+        assert!(backend.fetch_lists().unwrap().is_empty());
+        assert_eq!(backend.fetch_todos("Trash").unwrap()[0].name, "Buy boxes");
+        assert!(backend.delete_project(&lists[1].id).is_err());
+    }
+
+    #[test]
+    fn test_add_with_details() {
+        let backend = FileBackend::in_memory();
+        let tomorrow = today() + Days::new(1);
+        let todo = NewTodo {
+            tags: vec!["errand".to_string()],
+            when: Some(When::Date(tomorrow)),
+            deadline: Some(tomorrow),
+            notes: "2%".to_string(),
+            ..NewTodo::new("Buy milk")
+        };
+        backend.add_todo("Today", &todo).unwrap();
+        let piano = NewTodo {
+            when: Some(When::Someday),
+            ..NewTodo::new("Learn piano")
+        };
+        backend.add_todo("Today", &piano).unwrap();
+
+        assert!(backend.fetch_todos("Today").unwrap().is_empty());
+        let upcoming = backend.fetch_todos("Upcoming").unwrap();
+        assert_eq!(upcoming[0].tags, vec!["errand"]);
+        assert_eq!(upcoming[0].when, Some(tomorrow));
+        assert_eq!(upcoming[0].deadline, Some(tomorrow));
+        assert_eq!(backend.fetch_notes(&upcoming[0].id).unwrap(), "2%");
+        assert_eq!(backend.fetch_todos("Someday").unwrap()[0].name, "Learn piano");
+    }
+
+    #[test]
+    fn test_edit_tags() {
+        let backend = FileBackend::in_memory();
+        backend.add_todo("Today", &NewTodo::new("Do homework")).unwrap();
+        backend.add_todo("Today", &NewTodo::new("Write report")).unwrap();
+        let todos = backend.fetch_todos("Today").unwrap();
+
+        backend.edit_tags(&todos[0].id, &["homework", "school"], &[]).unwrap();
+        backend.edit_tags(&todos[1].id, &["work", "school"], &[]).unwrap();
+        backend.edit_tags(&todos[1].id, &["urgent"], &["school", "home"]).unwrap();
+
+        let todos = backend.fetch_todos("Today").unwrap();
+        assert_eq!(todos[0].tags, vec!["homework", "school"]);
+        assert_eq!(todos[1].tags, vec!["work", "urgent"]);
+        let tags = backend.fetch_tags().unwrap();
+        let school = tags.iter().find(|t| t.name == "school").unwrap();
+        assert_eq!(school.count, 1);
+        assert_eq!(tags.len(), 4);
+    }
+
+    #[test]
+    fn test_apply_batch() {
+        let path = std::env::temp_dir().join(format!("thingy-batch-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let backend = open(&path);
+        backend.add_todo("Today", &NewTodo::new("First")).unwrap();
+        backend.add_todo("Today", &NewTodo::new("Second")).unwrap();
+        let ids: Vec<String> = backend.fetch_todos("Today").unwrap().into_iter().map(|t| t.id).collect();
+
+        let results = backend.apply(&[
+            Op::Complete { id: ids[0].clone(), tags: Vec::new() },
+            Op::Delete { id: "missing".to_string() },
+            Op::Move { id: ids[1].clone(), to_list: "Someday".to_string() },
+        ]);
+        assert_eq!(results[0], Ok("First".to_string()));
+        assert!(results[1].is_err());
+        assert_eq!(results[2], Ok("Second".to_string()));
+
+        let reopened = open(&path);
+        assert!(reopened.fetch_todos("Today").unwrap().is_empty());
+        assert_eq!(reopened.fetch_todos("Someday").unwrap().len(), 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_persists_to_file() {
+        let path = std::env::temp_dir().join(format!("thingy-test-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let backend = open(&path);
+        backend.add_todo("Inbox", &NewTodo::new("Persist me")).unwrap();
+        let id = backend.fetch_todos("Inbox").unwrap()[0].id.clone();
+        backend.add_tag(&id, "home").unwrap();
+
+        let reopened = open(&path);
+        let todos = reopened.fetch_todos("Inbox").unwrap();
+        assert_eq!(todos[0].name, "Persist me");
+        assert_eq!(todos[0].tags, vec!["home"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
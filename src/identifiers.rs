@@ -1,43 +1,51 @@
 use std::collections::HashMap;
+use chrono::Local;
+use crate::config;
+use crate::registry::RegistryStore;
 use crate::todo::Todo;
 
-pub fn assign_identifiers(todos: &mut [Todo]) {
-    let mut seen_ids: HashMap<String, usize> = HashMap::new();
-
-    for todo in todos.iter_mut() {
-        let base_id = Todo::generate_base_identifier(&todo.name);
-        let collision_count = seen_ids.entry(base_id.clone()).or_insert(0);
-
-        if *collision_count > 999 {
-            panic!("Too many collisions for identifier {}", base_id);
-        }
+/// Gives `todos` identifiers of the configured length, keeping the ones
+/// `registry` says they were given before.
+pub fn assign_identifiers(todos: &mut [Todo], registry: &RegistryStore) {
+    let width = config::get().identifier_length;
+    registry.update(|registry| registry.assign(todos, width, Local::now().date_naive()));
+}
 
-        todo.identifier = if *collision_count == 0 {
-            base_id
-        } else {
-            resolve_collision(&base_id, *collision_count)
-        };
+/// Identifiers for `names`, in order, unique within the slice.  A
+/// collision keeps as much of the base as fits alongside its number.
+pub fn generate_identifiers(names: &[&str], width: usize) -> Vec<String> {
+    let mut seen_ids: HashMap<String, usize> = HashMap::new();
 
-        *collision_count += 1;
-    }
+    names
+        .iter()
+        .map(|name| {
+            let base_id = Todo::generate_base_identifier(name, width);
+            let collision_count = seen_ids.entry(base_id.clone()).or_insert(0);
+
+            if *collision_count > 999 {
+                panic!("Too many collisions for identifier {}", base_id);
+            }
+
+            let identifier = if *collision_count == 0 {
+                base_id
+            } else {
+                resolve_collision(&base_id, *collision_count)
+            };
+
+            *collision_count += 1;
+            identifier
+        })
+        .collect()
 }
 
-fn resolve_collision(base_id: &str, n: usize) -> String {
+pub fn resolve_collision(base_id: &str, n: usize) -> String {
+    let suffix = n.to_string();
     let base_len = base_id.chars().count();
-    match n {
-        1..=9 if base_len >= 2 => {
-            let prefix: String = base_id.chars().take(base_len - 1).collect();
-            format!("{}{}", prefix, n)
-        }
-        1..=9 => format!("{}", n),
-        10..=99 if base_len >= 2 => {
-            let prefix: String = base_id.chars().take(base_len.saturating_sub(2).max(0)).collect();
-            format!("{}{}", prefix, n)
-        }
-        10..=99 => format!("{}", n),
-        100..=999 => format!("{}", n),
-        _ => unreachable!(),
-    }
+    let prefix: String = base_id
+        .chars()
+        .take(base_len.saturating_sub(suffix.len()))
+        .collect();
+    format!("{}{}", prefix, suffix)
 }
 
 #[cfg(test)]
@@ -46,11 +54,13 @@ mod tests {
 
     fn make_todo(name: &str, index: usize) -> Todo {
         Todo {
+            id: format!("id-{}", index),
             name: name.to_string(),
-            tags: String::new(),
+            tags: Vec::new(),
             is_completed: false,
             index,
             identifier: String::new(),
+            ..Default::default()
         }
     }
 
@@ -62,7 +72,7 @@ mod tests {
             make_todo("Call mom", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "FOO");
         assert_eq!(todos[1].identifier, "BUY");
@@ -77,7 +87,7 @@ mod tests {
             make_todo("ab", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "HI");
         assert_eq!(todos[1].identifier, "A");
@@ -92,7 +102,7 @@ mod tests {
             make_todo("   \t\n", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "___");
         assert_eq!(todos[1].identifier, "__1");
@@ -106,7 +116,7 @@ mod tests {
             make_todo("456", 2),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "123");
         assert_eq!(todos[1].identifier, "456");
@@ -120,7 +130,7 @@ mod tests {
             make_todo("Hello again", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "HEL");
         assert_eq!(todos[1].identifier, "HE1");
@@ -133,7 +143,7 @@ mod tests {
             .map(|i| make_todo(&format!("Hello {}", i), i + 1))
             .collect();
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "HEL");
         assert_eq!(todos[1].identifier, "HE1");
@@ -149,7 +159,7 @@ mod tests {
             .map(|i| make_todo(&format!("Hello {}", i), i + 1))
             .collect();
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "HEL");
         assert_eq!(todos[99].identifier, "H99");
@@ -166,7 +176,7 @@ mod tests {
             make_todo("@mention", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "BIN");
         assert_eq!(todos[1].identifier, "CAL");
@@ -181,7 +191,7 @@ mod tests {
             make_todo("日本語", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "CAF");
         assert_eq!(todos[1].identifier, "NAÏ");
@@ -196,7 +206,7 @@ mod tests {
             make_todo("Ab", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "AB");
         assert_eq!(todos[1].identifier, "A1");
@@ -211,7 +221,7 @@ mod tests {
             make_todo("a ", 3),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         assert_eq!(todos[0].identifier, "A");
         assert_eq!(todos[1].identifier, "1");
@@ -228,7 +238,7 @@ mod tests {
             make_todo("Finish report", 5),
         ];
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
 
         let ids: Vec<String> = todos.iter().map(|t| t.identifier.clone()).collect();
         let unique_ids: std::collections::HashSet<_> = ids.iter().collect();
@@ -240,6 +250,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wider_identifiers() {
+        let ids = generate_identifiers(
+            &["Hello world", "Hello there", "Call mom", "", "ab"],
+            4,
+        );
+        assert_eq!(ids, vec!["HELL", "HEL1", "CALL", "____", "AB"]);
+
+        let names: Vec<String> = (0..12).map(|i| format!("Hello {}", i)).collect();
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        let ids = generate_identifiers(&names, 4);
+        assert_eq!(ids[10], "HE10");
+    }
+
+    #[test]
+    fn test_single_letter_identifiers() {
+        assert_eq!(generate_identifiers(&["Buy milk", "Bake"], 1), vec!["B", "1"]);
+    }
+
     #[test]
     #[should_panic(expected = "Too many collisions")]
     fn test_collision_limit() {
@@ -247,6 +276,6 @@ mod tests {
             .map(|i| make_todo(&format!("Hello {}", i), i + 1))
             .collect();
 
-        assign_identifiers(&mut todos);
+        assign_identifiers(&mut todos, &RegistryStore::in_memory());
     }
 }
@@ -1,29 +1,21 @@
 use std::io::{self, Write};
 
+use chrono::Local;
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     terminal, ExecutableCommand,
 };
 
-use crate::applescript::run_applescript;
-use crate::commands::fetch_todo_notes;
+use crate::backend::Backend;
+use crate::config;
+use crate::error::{Result, ThingyError};
+use crate::journal::Change;
+use crate::quickadd;
 use crate::todo::Todo;
 
-fn exit_with_error(message: &str) -> ! {
-    let _ = terminal::disable_raw_mode();
-    eprintln!("\n{}", message);
-    std::process::exit(1);
-}
-
 fn remove_inprogress_tag_from_local_state(todo: &mut Todo) {
-    if todo.tags.contains("in-progress") {
-        todo.tags = todo.tags
-            .split(", ")
-            .filter(|t| *t != "in-progress")
-            .collect::<Vec<_>>()
-            .join(", ");
-    }
+    todo.remove_tag(&config::get().tags.in_progress);
 }
 
 fn clear_and_redraw_all_todos(todos: &[Todo], selected_idx: usize) -> usize {
@@ -39,200 +31,27 @@ fn clear_and_redraw_all_todos(todos: &[Todo], selected_idx: usize) -> usize {
     todos.len()
 }
 
-fn fetch_all_todos() -> Result<Vec<Todo>, String> {
-    let script = r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    set allTodos to to dos of listToQuery
-    set output to ""
-    set oldDelimiters to AppleScript's text item delimiters
-    repeat with todo in allTodos
-        set todoName to name of todo
-        set todoStatus to status of todo
-        set todoTags to tag names of todo
-
-        if todoStatus is completed then
-            set statusFlag to "COMPLETED"
-        else
-            set statusFlag to "NOTCOMPLETED"
-        end if
-
-        if (count of todoTags) > 0 then
-            set AppleScript's text item delimiters to ", "
-            set tagString to todoTags as string
-            set AppleScript's text item delimiters to oldDelimiters
-            set output to output & statusFlag & "|" & todoName & "|" & tagString & "\n"
-        else
-            set output to output & statusFlag & "|" & todoName & "|\n"
-        end if
-    end repeat
-    set AppleScript's text item delimiters to oldDelimiters
-    return output
-end tell
-"#;
-
-    match run_applescript(script) {
-        Ok(result) => {
-            let mut todos = Vec::new();
-            for (idx, line) in result.trim().lines().enumerate() {
-                let parts: Vec<&str> = line.split('|').collect();
-                if parts.len() >= 2 {
-                    let is_completed = parts[0] == "COMPLETED";
-                    let name = parts[1].to_string();
-                    let tags = if parts.len() >= 3 {
-                        parts[2].to_string()
-                    } else {
-                        String::new()
-                    };
-                    todos.push(Todo {
-                        name,
-                        tags,
-                        is_completed,
-                        index: idx + 1,
-                        identifier: String::new(),
-                    });
-                }
-            }
-            crate::identifiers::assign_identifiers(&mut todos);
-            Ok(todos)
-        }
-        Err(e) => Err(e),
-    }
+fn fetch_all_todos(backend: &dyn Backend) -> Result<Vec<Todo>> {
+    backend.fetch_all(&config::get().default_list)
 }
 
-fn toggle_todo_completion(todo: &Todo) -> Result<(), String> {
-    let script = if todo.is_completed {
-        format!(
-            r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    set allTodos to to dos of listToQuery
-    if (count of allTodos) < {} then
-        error "Todo index {} is out of range"
-    end if
-    set todoToUpdate to item {} of allTodos
-    set status of todoToUpdate to open
-end tell
-"#,
-            todo.index, todo.index, todo.index
-        )
-    } else {
-        format!(
-            r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    set allTodos to to dos of listToQuery
-    if (count of allTodos) < {} then
-        error "Todo index {} is out of range"
-    end if
-    set todoToUpdate to item {} of allTodos
-
-    set currentTags to tag names of todoToUpdate
-    if currentTags contains "in-progress" then
-        set oldDelimiters to AppleScript's text item delimiters
-        set AppleScript's text item delimiters to ", "
-        set tagList to text items of currentTags
-        set newTagList to {{}}
-        repeat with i from 1 to (count of tagList)
-            set tagItem to (item i of tagList) as text
-            if tagItem is not "in-progress" then
-                set end of newTagList to tagItem
-            end if
-        end repeat
-        if (count of newTagList) > 0 then
-            set AppleScript's text item delimiters to ", "
-            set tag names of todoToUpdate to (newTagList as text)
-        else
-            set tag names of todoToUpdate to ""
-        end if
-        set AppleScript's text item delimiters to oldDelimiters
-    end if
-
-    set status of todoToUpdate to completed
-end tell
-"#,
-            todo.index, todo.index, todo.index
-        )
-    };
-
-    run_applescript(&script).map(|_| ())
+fn toggle_todo_completion(backend: &dyn Backend, todo: &Todo) -> Result<()> {
+    backend.set_completed(&todo.id, !todo.is_completed)?;
+    let action = if todo.is_completed { "reopen" } else { "complete" };
+    backend.journal().record(action, vec![Change::of(todo)]);
+    Ok(())
 }
 
-fn toggle_inprogress_tag(todo: &Todo) -> Result<String, String> {
-    let has_tag = todo.tags.split(", ").any(|t| t == "in-progress");
-
-    let script = if has_tag {
-        format!(
-            r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    set allTodos to to dos of listToQuery
-    if (count of allTodos) < {} then
-        error "Todo index {} is out of range"
-    end if
-    set todoToUpdate to item {} of allTodos
-
-    set currentTags to tag names of todoToUpdate
-    set oldDelimiters to AppleScript's text item delimiters
-    set AppleScript's text item delimiters to ", "
-    set tagList to text items of currentTags
-    set newTagList to {{}}
-    repeat with i from 1 to (count of tagList)
-        set tagItem to (item i of tagList) as text
-        if tagItem is not "in-progress" then
-            set end of newTagList to tagItem
-        end if
-    end repeat
-    if (count of newTagList) > 0 then
-        set AppleScript's text item delimiters to ", "
-        set tag names of todoToUpdate to (newTagList as text)
-    else
-        set tag names of todoToUpdate to ""
-    end if
-    set AppleScript's text item delimiters to oldDelimiters
-    return tag names of todoToUpdate
-end tell
-"#,
-            todo.index, todo.index, todo.index
-        )
-    } else {
-        format!(
-            r#"
-tell application "Things3"
-    set listToQuery to list "Today"
-    set allTodos to to dos of listToQuery
-    if (count of allTodos) < {} then
-        error "Todo index {} is out of range"
-    end if
-    set todoToUpdate to item {} of allTodos
-
-    set inProgressTag to missing value
-    try
-        set inProgressTag to tag "in-progress"
-    on error
-        set inProgressTag to make new tag with properties {{name:"in-progress"}}
-    end try
-
-    set currentTags to tag names of todoToUpdate
-    if currentTags is "" then
-        set tag names of todoToUpdate to "in-progress"
-    else if currentTags does not contain "in-progress" then
-        set tag names of todoToUpdate to currentTags & ", in-progress"
-    end if
-    return tag names of todoToUpdate
-end tell
-"#,
-            todo.index, todo.index, todo.index
-        )
-    };
-
-    run_applescript(&script)
+fn toggle_inprogress_tag(backend: &dyn Backend, todo: &Todo) -> Result<Vec<String>> {
+    let tags = backend.toggle_tag(&todo.id, &config::get().tags.in_progress)?;
+    backend.journal().record("tag", vec![Change::of(todo)]);
+    Ok(tags)
 }
 
 fn render_todo_line(todo: &Todo, is_selected: bool) -> String {
     let prefix = if is_selected { "> " } else { "  " };
     let todo_text = if !todo.tags.is_empty() {
-        format!("{} [{}]", todo.name, todo.tags)
+        format!("{} [{}]", todo.name, todo.tag_text())
     } else {
         todo.name.clone()
     };
@@ -280,42 +99,41 @@ fn redraw_from_top(todos: &[Todo], selected_idx: usize) {
     stdout.flush().unwrap();
 }
 
-fn log_completed_and_refresh(
+/// Refetches the todos and redraws them, unless there are none left.
+fn refresh(
+    backend: &dyn Backend,
     todos: &mut Vec<Todo>,
     selected_idx: &mut usize,
     displayed_count: &mut usize,
-) {
-    let script = r#"
-tell application "Things3"
-    log completed now
-end tell
-"#;
-    if let Err(e) = run_applescript(script) {
-        exit_with_error(&format!("Error logging completed: {}", e));
+) -> Result<()> {
+    *todos = fetch_all_todos(backend)?;
+    if *selected_idx >= todos.len() && !todos.is_empty() {
+        *selected_idx = todos.len() - 1;
     }
-
-    match fetch_all_todos() {
-        Ok(new_todos) => {
-            *todos = new_todos;
-            if *selected_idx >= todos.len() && todos.len() > 0 {
-                *selected_idx = todos.len() - 1;
-            }
-            if todos.is_empty() {
-                let _ = terminal::disable_raw_mode();
-                let _ = io::stdout().execute(cursor::Show);
-                println!("\nNo todos in Today list");
-                std::process::exit(0);
-            }
-            redraw_list(&todos, *selected_idx, *displayed_count);
-            *displayed_count = todos.len();
-        }
-        Err(e) => {
-            exit_with_error(&format!("Error refreshing todos: {}", e));
-        }
+    if !todos.is_empty() {
+        redraw_list(todos, *selected_idx, *displayed_count);
+        *displayed_count = todos.len();
     }
+    Ok(())
 }
 
-fn add_new_todo(todos: &[Todo], displayed_count: usize) -> Result<Option<String>, String> {
+fn log_completed_and_refresh(
+    backend: &dyn Backend,
+    todos: &mut Vec<Todo>,
+    selected_idx: &mut usize,
+    displayed_count: &mut usize,
+) -> Result<()> {
+    let logged: Vec<Change> = todos.iter().filter(|t| t.is_completed).map(Change::of).collect();
+    backend.log_completed()?;
+    backend.journal().record("log", logged);
+    refresh(backend, todos, selected_idx, displayed_count)
+}
+
+fn add_new_todo(
+    backend: &dyn Backend,
+    todos: &[Todo],
+    displayed_count: usize,
+) -> Result<Option<String>> {
     let mut stdout = io::stdout();
     let mut input = String::new();
 
@@ -328,7 +146,9 @@ fn add_new_todo(todos: &[Todo], displayed_count: usize) -> Result<Option<String>
     stdout.execute(terminal::Clear(terminal::ClearType::FromCursorDown)).unwrap();
 
     // Show input prompt at top:
-    print!("+ ___ ");
+    let width = config::get().identifier_length;
+    let placeholder = "_".repeat(width);
+    print!("+ {} ", placeholder);
     stdout.flush().unwrap();
 
     // Print all existing todos below:
@@ -338,9 +158,9 @@ fn add_new_todo(todos: &[Todo], displayed_count: usize) -> Result<Option<String>
     stdout.flush().unwrap();
 
     // Move cursor back to input line:
-    if todos.len() > 0 {
+    if !todos.is_empty() {
         stdout.execute(cursor::MoveUp(todos.len() as u16)).unwrap();
-        stdout.execute(cursor::MoveToColumn(6)).unwrap();
+        stdout.execute(cursor::MoveToColumn(3 + width as u16)).unwrap();
     }
     stdout.flush().unwrap();
 
@@ -357,36 +177,12 @@ fn add_new_todo(todos: &[Todo], displayed_count: usize) -> Result<Option<String>
                     input.push(c);
                     true
                 }
-                KeyCode::Backspace => {
-                    if !input.is_empty() {
-                        input.pop();
-                        true
-                    } else {
-                        false
-                    }
-                }
+                KeyCode::Backspace => input.pop().is_some(),
                 KeyCode::Enter => {
                     if !input.trim().is_empty() {
-                        // Escape backslashes and quotes for AppleScript string literal:
-                        let escaped_text = input.trim()
-                            .replace("\\", "\\\\")
-                            .replace("\"", "\\\"");
-
-                        let script = format!(
-                            r#"
-tell application "Things3"
-    set newTodo to make new to do with properties {{name:"{}"}}
-    move newTodo to list "Today"
-    return name of newTodo
-end tell
-"#,
-                            escaped_text
-                        );
-
-                        match run_applescript(&script) {
-                            Ok(name) => return Ok(Some(name.trim().to_string())),
-                            Err(e) => return Err(e),
-                        }
+                        let todo = quickadd::parse_line(&input, Local::now().date_naive())
+                            .map_err(ThingyError::Parse)?;
+                        return backend.add_todo(&config::get().default_list, &todo).map(Some);
                     } else {
                         return Ok(None);
                     }
@@ -399,9 +195,9 @@ end tell
 
             if should_update_display {
                 let identifier = if input.is_empty() {
-                    "___".to_string()
+                    placeholder.clone()
                 } else {
-                    Todo::generate_base_identifier(&input)
+                    Todo::generate_base_identifier(&input, width)
                 };
                 clear_line_and_print(&mut stdout, &format!("+ {} {}", identifier, input));
             }
@@ -409,7 +205,7 @@ end tell
     }
 }
 
-fn show_notes_view(todo: &Todo, displayed_count: usize) {
+fn show_notes_view(backend: &dyn Backend, todo: &Todo, displayed_count: usize) {
     let mut stdout = io::stdout();
 
     stdout.execute(cursor::MoveUp(displayed_count as u16)).unwrap();
@@ -418,7 +214,7 @@ fn show_notes_view(todo: &Todo, displayed_count: usize) {
     println!("{}\r", todo.name);
     println!("\r");
 
-    match fetch_todo_notes("Today", todo.index) {
+    match backend.fetch_notes(&todo.id) {
         Ok(notes) => {
             let trimmed_notes = notes.trim();
             if !trimmed_notes.is_empty() {
@@ -436,54 +232,50 @@ fn show_notes_view(todo: &Todo, displayed_count: usize) {
 
     loop {
         if let Ok(Event::Key(KeyEvent {
-            code,
-            modifiers: _,
-            kind: _,
-            state: _,
+            code: KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q'),
+            ..
         })) = event::read()
         {
-            match code {
-                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => {
-                    return;
-                }
-                _ => {}
-            }
+            return;
         }
     }
 }
 
-pub fn interactive_mode() {
-    let mut todos = match fetch_all_todos() {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("Error fetching todos: {}", e);
-            std::process::exit(1);
-        }
-    };
+pub fn interactive_mode(backend: &dyn Backend) -> Result<()> {
+    let mut todos = fetch_all_todos(backend)?;
 
     if todos.is_empty() {
-        println!("No todos in Today list");
-        return;
+        println!("No todos in {} list", config::get().default_list);
+        return Ok(());
     }
 
-    let mut selected_idx = 0;
-
     for (idx, todo) in todos.iter().enumerate() {
         let line = render_todo_line(todo, idx == 0);
         println!("{}", line);
     }
 
-    let mut displayed_count = todos.len();
+    terminal::enable_raw_mode()
+        .map_err(|e| ThingyError::Other(format!("Error enabling raw mode: {}", e)))?;
+    let _ = io::stdout().execute(cursor::Hide);
+
+    let result = run(backend, &mut todos);
 
-    if let Err(e) = terminal::enable_raw_mode() {
-        eprintln!("Error enabling raw mode: {}", e);
-        return;
+    let _ = io::stdout().execute(cursor::Show);
+    let _ = terminal::disable_raw_mode();
+    if result.is_err() {
+        println!();
+    } else if todos.is_empty() {
+        println!("\nNo todos in {} list", config::get().default_list);
     }
+    result
+}
 
-    let mut stdout = io::stdout();
-    let _ = stdout.execute(cursor::Hide);
+/// Handles keys until the user quits or there are no todos left.
+fn run(backend: &dyn Backend, todos: &mut Vec<Todo>) -> Result<()> {
+    let mut selected_idx = 0;
+    let mut displayed_count = todos.len();
 
-    loop {
+    while !todos.is_empty() {
         if let Ok(Event::Key(KeyEvent {
             code,
             modifiers,
@@ -496,31 +288,25 @@ pub fn interactive_mode() {
                     break;
                 }
                 (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
-                    displayed_count = clear_and_redraw_all_todos(&todos, selected_idx);
+                    displayed_count = clear_and_redraw_all_todos(todos, selected_idx);
                 }
                 (KeyCode::Enter, _) => {
                     let todo = &todos[selected_idx];
-                    show_notes_view(todo, displayed_count);
-                    displayed_count = clear_and_redraw_all_todos(&todos, selected_idx);
+                    show_notes_view(backend, todo, displayed_count);
+                    displayed_count = clear_and_redraw_all_todos(todos, selected_idx);
                 }
-                (KeyCode::Up, _) | (KeyCode::Char('k'), _) => {
-                    if selected_idx > 0 {
-                        selected_idx -= 1;
-                        redraw_list(&todos, selected_idx, displayed_count);
-                    }
+                (KeyCode::Up, _) | (KeyCode::Char('k'), _) if selected_idx > 0 => {
+                    selected_idx -= 1;
+                    redraw_list(todos, selected_idx, displayed_count);
                 }
-                (KeyCode::Down, _) | (KeyCode::Char('j'), _) => {
-                    if selected_idx < todos.len() - 1 {
-                        selected_idx += 1;
-                        redraw_list(&todos, selected_idx, displayed_count);
-                    }
+                (KeyCode::Down, _) | (KeyCode::Char('j'), _) if selected_idx < todos.len() - 1 => {
+                    selected_idx += 1;
+                    redraw_list(todos, selected_idx, displayed_count);
                 }
                 (KeyCode::Char(' '), _) | (KeyCode::Char('x'), _) => {
                     let todo = &todos[selected_idx];
                     let was_completed = todo.is_completed;
-                    if let Err(e) = toggle_todo_completion(todo) {
-                        exit_with_error(&format!("Error toggling todo: {}", e));
-                    }
+                    toggle_todo_completion(backend, todo)?;
 
                     todos[selected_idx].is_completed = !was_completed;
 
@@ -528,89 +314,44 @@ pub fn interactive_mode() {
                         remove_inprogress_tag_from_local_state(&mut todos[selected_idx]);
                     }
 
-                    redraw_list(&todos, selected_idx, displayed_count);
+                    redraw_list(todos, selected_idx, displayed_count);
                 }
                 (KeyCode::Char('/'), _) => {
                     let todo = &todos[selected_idx];
-                    match toggle_inprogress_tag(todo) {
-                        Ok(new_tags) => {
-                            todos[selected_idx].tags = new_tags.trim().to_string();
-                            redraw_list(&todos, selected_idx, displayed_count);
-                        }
-                        Err(e) => {
-                            exit_with_error(&format!("Error toggling in-progress tag: {}", e));
-                        }
-                    }
+                    todos[selected_idx].tags = toggle_inprogress_tag(backend, todo)?;
+                    redraw_list(todos, selected_idx, displayed_count);
                 }
                 (KeyCode::Char('r'), _) | (KeyCode::Char('g'), _) => {
-                    match fetch_all_todos() {
-                        Ok(new_todos) => {
-                            todos = new_todos;
-                            if selected_idx >= todos.len() && todos.len() > 0 {
-                                selected_idx = todos.len() - 1;
-                            }
-                            if todos.is_empty() {
-                                let _ = terminal::disable_raw_mode();
-                                let _ = io::stdout().execute(cursor::Show);
-                                println!("\nNo todos in Today list");
-                                return;
-                            }
-                            redraw_list(&todos, selected_idx, displayed_count);
-                            displayed_count = todos.len();
-                        }
-                        Err(e) => {
-                            exit_with_error(&format!("Error refreshing todos: {}", e));
-                        }
-                    }
+                    refresh(backend, todos, &mut selected_idx, &mut displayed_count)?;
                 }
                 (KeyCode::Char('L'), _) => {
-                    log_completed_and_refresh(&mut todos, &mut selected_idx, &mut displayed_count);
+                    log_completed_and_refresh(backend, todos, &mut selected_idx, &mut displayed_count)?;
                 }
                 (KeyCode::Char('X'), _) => {
                     let todo = &todos[selected_idx];
                     let was_completed = todo.is_completed;
-
-                    if let Err(e) = toggle_todo_completion(todo) {
-                        exit_with_error(&format!("Error toggling todo: {}", e));
-                    }
+                    toggle_todo_completion(backend, todo)?;
 
                     if !was_completed {
                         todos[selected_idx].is_completed = true;
                         remove_inprogress_tag_from_local_state(&mut todos[selected_idx]);
-                        log_completed_and_refresh(&mut todos, &mut selected_idx, &mut displayed_count);
+                        log_completed_and_refresh(backend, todos, &mut selected_idx, &mut displayed_count)?;
                     } else {
                         todos[selected_idx].is_completed = false;
-                        redraw_list(&todos, selected_idx, displayed_count);
+                        redraw_list(todos, selected_idx, displayed_count);
                     }
                 }
                 (KeyCode::Char('+'), _) => {
-                    match add_new_todo(&todos, displayed_count) {
-                        Ok(Some(_)) => {
-                            match fetch_all_todos() {
-                                Ok(new_todos) => {
-                                    todos = new_todos;
-                                    selected_idx = 0;
-                                    redraw_from_top(&todos, selected_idx);
-                                    displayed_count = todos.len();
-                                }
-                                Err(e) => {
-                                    exit_with_error(&format!("Error refreshing todos: {}", e));
-                                }
-                            }
-                        }
-                        Ok(None) => {
-                            redraw_from_top(&todos, selected_idx);
-                        }
-                        Err(e) => {
-                            exit_with_error(&format!("Error adding todo: {}", e));
-                        }
+                    if add_new_todo(backend, todos, displayed_count)?.is_some() {
+                        *todos = fetch_all_todos(backend)?;
+                        selected_idx = 0;
                     }
+                    redraw_from_top(todos, selected_idx);
+                    displayed_count = todos.len();
                 }
                 _ => {}
             }
         }
     }
-
-    let _ = io::stdout().execute(cursor::Show);
-    let _ = terminal::disable_raw_mode();
+    Ok(())
 }
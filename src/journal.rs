@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::todo::Todo;
+
+/// Entries kept; older ones are dropped as new ones are recorded.
+const MAX_ENTRIES: usize = 100;
+
+/// One command's changes, for `thingy history` and `thingy undo`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: NaiveDateTime,
+    /// What was done, e.g. "complete".
+    pub action: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub id: String,
+    pub name: String,
+    pub before: Snapshot,
+}
+
+/// The parts of a todo our commands change, as they were beforehand.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub list: String,
+    pub completed: bool,
+    pub tags: Vec<String>,
+    pub when: Option<NaiveDate>,
+    pub deadline: Option<NaiveDate>,
+}
+
+impl Change {
+    pub fn of(todo: &Todo) -> Self {
+        Change {
+            id: todo.id.clone(),
+            name: todo.name.clone(),
+            before: Snapshot {
+                list: todo.list.clone(),
+                completed: todo.is_completed,
+                tags: todo.tags.clone(),
+                when: todo.when,
+                deadline: todo.deadline,
+            },
+        }
+    }
+}
+
+impl Entry {
+    /// E.g. "complete: Buy milk, Call mom".
+    pub fn summary(&self) -> String {
+        let names: Vec<&str> = self.changes.iter().map(|c| c.name.as_str()).collect();
+        format!("{}: {}", self.action, names.join(", "))
+    }
+}
+
+fn journal_path() -> PathBuf {
+    if let Ok(path) = env::var("THINGY_JOURNAL") {
+        return PathBuf::from(path);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".thingy-journal.json")
+}
+
+/// Where entries are kept: the journal file, or memory for a backend
+/// that keeps nothing on disk.  Clones share their entries.
+#[derive(Debug, Clone)]
+pub enum Journal {
+    File(PathBuf),
+    Memory(Rc<RefCell<Vec<Entry>>>),
+}
+
+impl Journal {
+    /// The file at `$THINGY_JOURNAL`, else `~/.thingy-journal.json`.
+    pub fn from_env() -> Self {
+        Journal::File(journal_path())
+    }
+
+    pub fn in_memory() -> Self {
+        Journal::Memory(Rc::default())
+    }
+
+    fn read(&self) -> Result<Vec<Entry>, String> {
+        let path = match self {
+            Journal::File(path) => path,
+            Journal::Memory(entries) => return Ok(entries.borrow().clone()),
+        };
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Error parsing {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("Error reading {}: {}", path.display(), e)),
+        }
+    }
+
+    fn write(&self, entries: &[Entry]) -> Result<(), String> {
+        let path = match self {
+            Journal::File(path) => path,
+            Journal::Memory(stored) => {
+                *stored.borrow_mut() = entries.to_vec();
+                return Ok(());
+            }
+        };
+        let json = serde_json::to_string_pretty(entries)
+            .map_err(|e| format!("Error serializing journal: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+    }
+
+    /// Recorded entries, oldest first.
+    pub fn entries(&self) -> Result<Vec<Entry>, String> {
+        self.read()
+    }
+
+    /// Adds an entry for `changes`.  The change itself has already
+    /// happened, so a journal that can't be written is only a warning.
+    pub fn record(&self, action: &str, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        let result = self.read().and_then(|mut entries| {
+            entries.push(Entry {
+                time: Local::now().naive_local(),
+                action: action.to_string(),
+                changes,
+            });
+            let excess = entries.len().saturating_sub(MAX_ENTRIES);
+            entries.drain(..excess);
+            self.write(&entries)
+        });
+        if let Err(error) = result {
+            eprintln!("Warning: couldn't record this for undo: {}", error);
+        }
+    }
+
+    /// Drops the most recent entry, once it's been undone.
+    pub fn pop(&self) -> Result<(), String> {
+        let mut entries = self.read()?;
+        entries.pop();
+        self.write(&entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(name: &str) -> Todo {
+        Todo {
+            id: format!("id-{}", name),
+            name: name.to_string(),
+            list: "Today".to_string(),
+            tags: vec!["errand".to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// A journal in a file of its own, removed first.
+    fn temp_journal(name: &str) -> Journal {
+        let path = env::temp_dir().join(format!("thingy-journal-{}-{}.json", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        Journal::File(path)
+    }
+
+    #[test]
+    fn test_record_and_pop() {
+        let journal = temp_journal("record");
+        journal.record("complete", vec![Change::of(&todo("Buy milk")), Change::of(&todo("Call mom"))]);
+        journal.record("rm", vec![Change::of(&todo("Fix bike"))]);
+        journal.record("tag", Vec::new());
+
+        let recorded = journal.entries().unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].summary(), "complete: Buy milk, Call mom");
+        assert_eq!(recorded[1].changes[0].before.list, "Today");
+        assert_eq!(recorded[1].changes[0].before.tags, vec!["errand"]);
+
+        journal.pop().unwrap();
+        assert_eq!(journal.entries().unwrap().len(), 1);
+        if let Journal::File(path) = journal {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_oldest_entries_dropped() {
+        let journal = Journal::in_memory();
+        for n in 0..MAX_ENTRIES + 5 {
+            journal.record("rm", vec![Change::of(&todo(&n.to_string()))]);
+        }
+        let recorded = journal.entries().unwrap();
+        assert_eq!(recorded.len(), MAX_ENTRIES);
+        assert_eq!(recorded[0].changes[0].name, "5");
+    }
+}
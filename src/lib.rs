@@ -0,0 +1,48 @@
+//! thingy's todo commands as a library.  `Client` is the way in; the
+//! `thingy` binary is a thin CLI over `cli::run`.
+//!
+//! ```
+//! use thingy::{Client, FileBackend};
+//!
+//! let client = Client::new(Box::new(FileBackend::in_memory()));
+//! client.add("today", "Buy milk #errand").unwrap();
+//! let todos = client.list("today").unwrap();
+//! assert_eq!(todos[0].name, "Buy milk");
+//! ```
+
+mod applescript;
+pub mod backend;
+mod cache;
+pub mod cli;
+mod client;
+mod commands;
+mod completion;
+mod config;
+pub mod error;
+pub mod file_backend;
+mod identifiers;
+mod interactive;
+mod journal;
+pub mod lists;
+mod matcher;
+mod output;
+mod query;
+mod quickadd;
+mod records;
+mod registry;
+pub mod tags;
+pub mod things;
+pub mod things_db;
+pub mod things_url;
+pub mod todo;
+
+pub use backend::{Backend, Op};
+pub use client::Client;
+pub use error::{Result, ThingyError};
+pub use file_backend::FileBackend;
+pub use journal::{Journal, Snapshot};
+pub use registry::RegistryStore;
+pub use things::ThingsBackend;
+pub use things_db::ThingsDbBackend;
+pub use things_url::ThingsUrlBackend;
+pub use todo::{NewTodo, Todo, When};
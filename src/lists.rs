@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::error::{Result, ThingyError};
+
+pub const BUILT_IN_LISTS: &[&str] = &[
+    "Inbox", "Today", "Anytime", "Upcoming", "Someday", "Logbook", "Trash",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListKind {
+    #[default]
+    BuiltIn,
+    Area,
+    Project,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListInfo {
+    /// Stable backend id of an area or project; empty for built-in lists.
+    pub id: String,
+    pub name: String,
+    pub kind: ListKind,
+    /// The area a project belongs to, if any.
+    pub area: Option<String>,
+    /// Short handle, as for todos (see `assign_list_identifiers`).
+    pub identifier: String,
+}
+
+pub fn is_built_in(name: &str) -> bool {
+    BUILT_IN_LISTS.contains(&name)
+}
+
+pub fn built_in_lists() -> Vec<ListInfo> {
+    BUILT_IN_LISTS
+        .iter()
+        .map(|name| ListInfo {
+            name: name.to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+pub fn assign_list_identifiers(lists: &mut [ListInfo]) {
+    let names: Vec<&str> = lists.iter().map(|l| l.name.as_str()).collect();
+    let width = crate::config::get().identifier_length;
+    let identifiers = crate::identifiers::generate_identifiers(&names, width);
+    for (list, identifier) in lists.iter_mut().zip(identifiers) {
+        list.identifier = identifier;
+    }
+}
+
+/// Finds a list by identifier, 1-based position, or exact (case-insensitive)
+/// name.
+pub fn find_list<'a>(lists: &'a [ListInfo], id_str: &str) -> Option<&'a ListInfo> {
+    if let Ok(n) = id_str.parse::<usize>() {
+        if n >= 1 && n <= lists.len() {
+            return Some(&lists[n - 1]);
+        }
+    }
+    let wanted = id_str.to_uppercase();
+    lists
+        .iter()
+        .find(|l| l.identifier == wanted)
+        .or_else(|| lists.iter().find(|l| l.name.to_lowercase() == id_str.to_lowercase()))
+}
+
+/// Finds the list called `name` among `lists`: a case-insensitive exact
+/// match, or failing that (when `allow_prefix` is set) the only list whose
+/// name starts with `name`.  An ambiguous prefix is an error.
+pub fn match_list_name(name: &str, lists: &[ListInfo], allow_prefix: bool)
+    -> Result<Option<String>>
+{
+    let wanted = name.to_lowercase();
+    if let Some(list) = lists.iter().find(|l| l.name.to_lowercase() == wanted) {
+        return Ok(Some(list.name.clone()));
+    }
+    if !allow_prefix || wanted.is_empty() {
+        return Ok(None);
+    }
+
+    let candidates: Vec<&str> = lists
+        .iter()
+        .filter(|l| l.name.to_lowercase().starts_with(&wanted))
+        .map(|l| l.name.as_str())
+        .collect();
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(candidates[0].to_string())),
+        _ => Err(ThingyError::usage(
+            format!("List '{}' is ambiguous: {}", name, candidates.join(", ")),
+            None,
+        )),
+    }
+}
+
+/// Like `match_list_name` over the built-in lists and the backend's areas
+/// and projects.  Only asks the backend when `name` isn't a built-in list.
+pub fn resolve_list(backend: &dyn Backend, name: &str, allow_prefix: bool)
+    -> Result<Option<String>>
+{
+    let built_in = built_in_lists();
+    if let Some(list) = match_list_name(name, &built_in, false)? {
+        return Ok(Some(list));
+    }
+
+    let mut lists = built_in;
+    lists.extend(backend.fetch_lists()?);
+    match_list_name(name, &lists, allow_prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists() -> Vec<ListInfo> {
+        let mut lists = built_in_lists();
+        for (name, kind) in [
+            ("Work", ListKind::Area),
+            ("Website relaunch", ListKind::Project),
+            ("Wedding", ListKind::Project),
+            ("Tax return", ListKind::Project),
+            ("Workshop prep", ListKind::Project),
+        ] {
+            lists.push(ListInfo {
+                name: name.to_string(),
+                kind,
+                ..Default::default()
+            });
+        }
+        lists
+    }
+
+    #[test]
+    fn test_exact_match_is_case_insensitive() {
+        assert_eq!(match_list_name("someday", &lists(), false), Ok(Some("Someday".to_string())));
+        assert_eq!(match_list_name("WORK", &lists(), false), Ok(Some("Work".to_string())));
+    }
+
+    #[test]
+    fn test_exact_match_beats_prefix() {
+        assert_eq!(match_list_name("work", &lists(), true), Ok(Some("Work".to_string())));
+        assert_eq!(match_list_name("works", &lists(), true), Ok(Some("Workshop prep".to_string())));
+        assert!(match_list_name("wor", &lists(), true).is_err());
+    }
+
+    #[test]
+    fn test_unique_prefix() {
+        assert_eq!(match_list_name("tax", &lists(), true), Ok(Some("Tax return".to_string())));
+        assert_eq!(match_list_name("webs", &lists(), true), Ok(Some("Website relaunch".to_string())));
+        assert_eq!(match_list_name("up", &lists(), true), Ok(Some("Upcoming".to_string())));
+    }
+
+    #[test]
+    fn test_prefix_not_allowed() {
+        assert_eq!(match_list_name("tax", &lists(), false), Ok(None));
+    }
+
+    #[test]
+    fn test_ambiguous_prefix() {
+        let err = match_list_name("we", &lists(), true).unwrap_err().to_string();
+        assert!(err.contains("Website relaunch"));
+        assert!(err.contains("Wedding"));
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert_eq!(match_list_name("groceries", &lists(), true), Ok(None));
+        assert_eq!(match_list_name("", &lists(), true), Ok(None));
+    }
+
+    #[test]
+    fn test_find_list() {
+        let mut projects: Vec<ListInfo> = lists()
+            .into_iter()
+            .filter(|l| l.kind == ListKind::Project)
+            .collect();
+        assign_list_identifiers(&mut projects);
+
+        assert_eq!(projects[0].identifier, "WEB");
+        assert_eq!(projects[1].identifier, "WED");
+        assert_eq!(find_list(&projects, "wed").unwrap().name, "Wedding");
+        assert_eq!(find_list(&projects, "3").unwrap().name, "Tax return");
+        assert_eq!(find_list(&projects, "tax return").unwrap().name, "Tax return");
+        assert!(find_list(&projects, "9").is_none());
+        assert!(find_list(&projects, "groceries").is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::todo::Todo;
+
+#[derive(Debug)]
+pub enum Match<'a> {
+    Unique(&'a Todo),
+    /// Several todos match equally well, best first.
+    Ambiguous(Vec<&'a Todo>),
+    /// Todos only matched loosely, closest first; even a single one should
+    /// be confirmed before acting on it.
+    Guess(Vec<&'a Todo>),
+    None,
+}
+
+/// Whether `query` is a list position rather than an identifier or name.
+pub fn is_position(query: &str) -> bool {
+    let query = query.trim();
+    !query.is_empty() && query.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Finds the todo `query` refers to.  Tries, in order, and stops at the
+/// first kind of match that finds anything:
+///
+/// 1. a list position (`3`); a query of digits only goes no further
+/// 2. an identifier (`BUY`, any case)
+/// 3. the start of identifiers (`BU`)
+/// 4. part of names (`milk`); a name equal to the query wins outright
+/// 5. the query's letters, in order, in names (`bymlk`), as a guess
+pub fn match_todo<'a>(todos: &'a [Todo], query: &str) -> Match<'a> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Match::None;
+    }
+
+    if is_position(query) {
+        return match query.parse::<usize>() {
+            Ok(n) if n > 0 => todos
+                .iter()
+                .find(|t| t.index == n)
+                .map_or(Match::None, Match::Unique),
+            _ => Match::None,
+        };
+    }
+
+    let upper = query.to_uppercase();
+    if let Some(todo) = todos.iter().find(|t| t.identifier == upper) {
+        return Match::Unique(todo);
+    }
+
+    let prefixed: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| t.identifier.starts_with(&upper))
+        .collect();
+    if !prefixed.is_empty() {
+        return from_candidates(prefixed);
+    }
+
+    let lower = query.to_lowercase();
+    if let Some(todo) = todos.iter().find(|t| t.name.to_lowercase() == lower) {
+        return Match::Unique(todo);
+    }
+    let containing: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| t.name.to_lowercase().contains(&lower))
+        .collect();
+    if !containing.is_empty() {
+        return from_candidates(containing);
+    }
+
+    let mut fuzzy: Vec<(usize, &Todo)> = todos
+        .iter()
+        .filter_map(|t| fuzzy_score(&t.name.to_lowercase(), &lower).map(|score| (score, t)))
+        .collect();
+    fuzzy.sort_by_key(|(score, _)| *score);
+    match fuzzy.len() {
+        0 => Match::None,
+        _ => Match::Guess(fuzzy.into_iter().map(|(_, t)| t).collect()),
+    }
+}
+
+fn from_candidates(mut candidates: Vec<&Todo>) -> Match<'_> {
+    match candidates.len() {
+        0 => Match::None,
+        1 => Match::Unique(candidates.remove(0)),
+        _ => Match::Ambiguous(candidates),
+    }
+}
+
+/// If `query`'s characters (ignoring spaces) appear in order in `name`,
+/// how spread out they are: lower is a closer match.
+fn fuzzy_score(name: &str, query: &str) -> Option<usize> {
+    let mut wanted = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    let mut first = None;
+    let mut last = 0;
+    for (pos, c) in name.chars().enumerate() {
+        if wanted.peek() == Some(&c) {
+            wanted.next();
+            first.get_or_insert(pos);
+            last = pos;
+        }
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+    first.map(|first| last - first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todos() -> Vec<Todo> {
+        [
+            ("Buy milk", "BUY"),
+            ("Buy bread", "BU1"),
+            ("Call mom", "CAL"),
+            ("Fix bike", "FIX"),
+            ("Fix", "FI1"),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (name, identifier))| Todo {
+            id: format!("id-{}", i + 1),
+            name: name.to_string(),
+            identifier: identifier.to_string(),
+            index: i + 1,
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    fn unique(todos: &[Todo], query: &str) -> String {
+        match match_todo(todos, query) {
+            Match::Unique(todo) => todo.name.clone(),
+            other => panic!("'{}' gave {:?}", query, other),
+        }
+    }
+
+    fn ambiguous(todos: &[Todo], query: &str) -> Vec<String> {
+        match match_todo(todos, query) {
+            Match::Ambiguous(candidates) => candidates.iter().map(|t| t.name.clone()).collect(),
+            other => panic!("'{}' gave {:?}", query, other),
+        }
+    }
+
+    fn guesses(todos: &[Todo], query: &str) -> Vec<String> {
+        match match_todo(todos, query) {
+            Match::Guess(candidates) => candidates.iter().map(|t| t.name.clone()).collect(),
+            other => panic!("'{}' gave {:?}", query, other),
+        }
+    }
+
+    #[test]
+    fn test_position_and_identifier() {
+        let todos = todos();
+        assert_eq!(unique(&todos, "3"), "Call mom");
+        assert_eq!(unique(&todos, "bu1"), "Buy bread");
+        assert_eq!(unique(&todos, "BUY"), "Buy milk");
+    }
+
+    #[test]
+    fn test_identifier_prefix() {
+        let todos = todos();
+        assert_eq!(unique(&todos, "ca"), "Call mom");
+        assert_eq!(ambiguous(&todos, "b"), vec!["Buy milk", "Buy bread"]);
+    }
+
+    #[test]
+    fn test_name_substring() {
+        let todos = todos();
+        assert_eq!(unique(&todos, "milk"), "Buy milk");
+        assert_eq!(unique(&todos, "MOM"), "Call mom");
+        assert_eq!(ambiguous(&todos, "i"), vec!["Buy milk", "Fix bike", "Fix"]);
+    }
+
+    #[test]
+    fn test_exact_name_wins() {
+        let todos = todos();
+        // An identifier beats a name:
+        assert_eq!(unique(&todos, "fix"), "Fix bike");
+        let todos: Vec<Todo> = todos.into_iter().filter(|t| t.identifier != "FIX").collect();
+        assert_eq!(unique(&todos, "fix"), "Fix");
+    }
+
+    #[test]
+    fn test_fuzzy() {
+        let todos = todos();
+        assert_eq!(guesses(&todos, "bymlk"), vec!["Buy milk"]);
+        assert_eq!(guesses(&todos, "cl mm"), vec!["Call mom"]);
+        assert_eq!(guesses(&todos, "ubd"), vec!["Buy bread"]);
+    }
+
+    #[test]
+    fn test_fuzzy_ranks_closer_matches_first() {
+        let todos = todos();
+        assert_eq!(guesses(&todos, "bk"), vec!["Fix bike", "Buy milk"]);
+    }
+
+    #[test]
+    fn test_numbers_are_only_positions() {
+        let mut todos = todos();
+        todos[2].name = "Meet at 5pm".to_string();
+        todos[3].identifier = "5".to_string();
+        assert!(matches!(match_todo(&todos, "5"), Match::Unique(t) if t.name == "Fix"));
+        assert!(matches!(match_todo(&todos, "6"), Match::None));
+        assert!(matches!(match_todo(&todos, "0"), Match::None));
+        assert!(matches!(match_todo(&todos, "99999999999999999999999"), Match::None));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let todos = todos();
+        assert!(matches!(match_todo(&todos, "zzz"), Match::None));
+        assert!(matches!(match_todo(&todos, "9"), Match::None));
+        assert!(matches!(match_todo(&todos, " "), Match::None));
+        assert!(matches!(match_todo(&[], "buy"), Match::None));
+    }
+}
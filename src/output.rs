@@ -0,0 +1,330 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::journal::Entry;
+use crate::lists::{ListInfo, ListKind};
+use crate::tags::TagInfo;
+use crate::todo::Todo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+    Tsv,
+}
+
+/// The serialized form of a todo, shared by every listing command.
+#[derive(Serialize)]
+struct TodoRecord<'a> {
+    identifier: &'a str,
+    id: &'a str,
+    name: &'a str,
+    tags: Vec<&'a str>,
+    completed: bool,
+    list: &'a str,
+    when: Option<NaiveDate>,
+    deadline: Option<NaiveDate>,
+    created: Option<NaiveDate>,
+    completed_on: Option<NaiveDate>,
+    project: Option<&'a str>,
+    notes: &'a str,
+}
+
+impl<'a> TodoRecord<'a> {
+    fn new(todo: &'a Todo) -> Self {
+        TodoRecord {
+            identifier: &todo.identifier,
+            id: &todo.id,
+            name: &todo.name,
+            tags: todo.tags.iter().map(|t| t.as_str()).collect(),
+            completed: todo.is_completed,
+            list: &todo.list,
+            when: todo.when,
+            deadline: todo.deadline,
+            created: todo.created,
+            completed_on: todo.completed_on,
+            project: todo.project.as_deref(),
+            notes: &todo.notes,
+        }
+    }
+}
+
+fn tsv_field(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn tsv_date(date: Option<NaiveDate>) -> String {
+    date.map(|d| d.to_string()).unwrap_or_default()
+}
+
+/// Renders `records` as a JSON array, or as TSV lines of the fields
+/// `tsv_row` picks.  Not for `Format::Text`, whose layout differs per
+/// command.
+fn render_records<T: Serialize>(
+    records: &[T],
+    format: Format,
+    tsv_row: impl Fn(&T) -> Vec<String>,
+) -> String {
+    match format {
+        Format::Json => serde_json::to_string_pretty(records).unwrap_or_default() + "\n",
+        Format::Tsv => records.iter().map(|r| tsv_row(r).join("\t") + "\n").collect(),
+        Format::Text => unreachable!("text output is rendered by each command"),
+    }
+}
+
+/// Renders todos as a JSON array or as TSV lines.
+pub fn render(todos: &[&Todo], format: Format) -> String {
+    let records: Vec<TodoRecord> = todos.iter().map(|t| TodoRecord::new(t)).collect();
+    render_records(&records, format, |record| {
+        vec![
+            tsv_field(record.identifier),
+            tsv_field(record.id),
+            tsv_field(record.name),
+            tsv_field(&record.tags.join(",")),
+            record.completed.to_string(),
+            tsv_field(record.list),
+            tsv_date(record.when),
+            tsv_date(record.deadline),
+            tsv_date(record.created),
+            tsv_date(record.completed_on),
+            tsv_field(record.project.unwrap_or("")),
+            tsv_field(record.notes),
+        ]
+    })
+}
+
+#[derive(Serialize)]
+struct ListRecord<'a> {
+    identifier: &'a str,
+    id: &'a str,
+    name: &'a str,
+    kind: &'static str,
+    area: Option<&'a str>,
+}
+
+/// Renders lists, areas and projects as a JSON array or as TSV lines.
+pub fn render_lists(lists: &[ListInfo], format: Format) -> String {
+    let records: Vec<ListRecord> = lists
+        .iter()
+        .map(|list| ListRecord {
+            identifier: &list.identifier,
+            id: &list.id,
+            name: &list.name,
+            kind: match list.kind {
+                ListKind::BuiltIn => "list",
+                ListKind::Area => "area",
+                ListKind::Project => "project",
+            },
+            area: list.area.as_deref(),
+        })
+        .collect();
+    render_records(&records, format, |record| {
+        vec![
+            tsv_field(record.identifier),
+            tsv_field(record.id),
+            tsv_field(record.name),
+            record.kind.to_string(),
+            tsv_field(record.area.unwrap_or("")),
+        ]
+    })
+}
+
+/// Renders tags, with their parents and open todo counts, as a JSON
+/// array or as TSV lines.
+pub fn render_tags(tags: &[TagInfo], format: Format) -> String {
+    render_records(tags, format, |tag| {
+        vec![
+            tsv_field(&tag.name),
+            tsv_field(tag.parent.as_deref().unwrap_or("")),
+            tag.count.to_string(),
+        ]
+    })
+}
+
+#[derive(Serialize)]
+struct CountRecord<'a> {
+    list: &'a str,
+    count: usize,
+}
+
+/// Renders the number of open todos in `list` as a JSON object or a TSV
+/// line.
+pub fn render_count(list: &str, count: usize, format: Format) -> String {
+    let record = CountRecord { list, count };
+    match format {
+        Format::Json => serde_json::to_string_pretty(&record).unwrap_or_default() + "\n",
+        _ => render_records(&[record], format, |r| vec![tsv_field(r.list), r.count.to_string()]),
+    }
+}
+
+#[derive(Serialize)]
+struct HistoryRecord<'a> {
+    time: NaiveDateTime,
+    action: &'a str,
+    todos: Vec<HistoryTodo<'a>>,
+}
+
+#[derive(Serialize)]
+struct HistoryTodo<'a> {
+    id: &'a str,
+    name: &'a str,
+}
+
+/// Renders journal entries, in the order given, as a JSON array or as TSV
+/// lines of time, action and the changed todos' names.
+pub fn render_history(entries: &[&Entry], format: Format) -> String {
+    let records: Vec<HistoryRecord> = entries
+        .iter()
+        .map(|entry| HistoryRecord {
+            time: entry.time,
+            action: &entry.action,
+            todos: entry
+                .changes
+                .iter()
+                .map(|change| HistoryTodo { id: &change.id, name: &change.name })
+                .collect(),
+        })
+        .collect();
+    render_records(&records, format, |record| {
+        let names: Vec<&str> = record.todos.iter().map(|t| t.name).collect();
+        vec![
+            record.time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            tsv_field(record.action),
+            tsv_field(&names.join(",")),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_todos() -> Vec<Todo> {
+        vec![
+            Todo {
+                id: "A1b2".to_string(),
+                name: "Buy milk".to_string(),
+                tags: vec!["errand".to_string(), "15m".to_string()],
+                index: 1,
+                identifier: "BUY".to_string(),
+                list: "Today".to_string(),
+                when: NaiveDate::from_ymd_opt(2026, 10, 17),
+                deadline: NaiveDate::from_ymd_opt(2026, 11, 1),
+                created: NaiveDate::from_ymd_opt(2026, 10, 1),
+                project: Some("Errands".to_string()),
+                notes: "2%".to_string(),
+                ..Default::default()
+            },
+            Todo {
+                id: "C3d4".to_string(),
+                name: "Tab\there\nnewline".to_string(),
+                is_completed: true,
+                index: 2,
+                identifier: "TAB".to_string(),
+                list: "Today".to_string(),
+                completed_on: NaiveDate::from_ymd_opt(2026, 10, 17),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_json_golden() {
+        let todos = sample_todos();
+        let refs: Vec<&Todo> = todos.iter().collect();
+        let expected = r#"[
+  {
+    "identifier": "BUY",
+    "id": "A1b2",
+    "name": "Buy milk",
+    "tags": [
+      "errand",
+      "15m"
+    ],
+    "completed": false,
+    "list": "Today",
+    "when": "2026-10-17",
+    "deadline": "2026-11-01",
+    "created": "2026-10-01",
+    "completed_on": null,
+    "project": "Errands",
+    "notes": "2%"
+  },
+  {
+    "identifier": "TAB",
+    "id": "C3d4",
+    "name": "Tab\there\nnewline",
+    "tags": [],
+    "completed": true,
+    "list": "Today",
+    "when": null,
+    "deadline": null,
+    "created": null,
+    "completed_on": "2026-10-17",
+    "project": null,
+    "notes": ""
+  }
+]
+"#;
+        assert_eq!(render(&refs, Format::Json), expected);
+    }
+
+    #[test]
+    fn test_render_json_empty() {
+        assert_eq!(render(&[], Format::Json), "[]\n");
+    }
+
+    #[test]
+    fn test_render_tsv_golden() {
+        let todos = sample_todos();
+        let refs: Vec<&Todo> = todos.iter().collect();
+        let expected = "BUY\tA1b2\tBuy milk\terrand,15m\tfalse\tToday\t2026-10-17\t2026-11-01\t2026-10-01\t\tErrands\t2%\n\
+                        TAB\tC3d4\tTab\\there\\nnewline\t\ttrue\tToday\t\t\t\t2026-10-17\t\t\n";
+        assert_eq!(render(&refs, Format::Tsv), expected);
+    }
+
+    #[test]
+    fn test_render_lists() {
+        let lists = vec![
+            ListInfo {
+                name: "Today".to_string(),
+                ..Default::default()
+            },
+            ListInfo {
+                id: "P1".to_string(),
+                name: "Garden".to_string(),
+                kind: ListKind::Project,
+                area: Some("Home".to_string()),
+                identifier: "GAR".to_string(),
+            },
+        ];
+        assert_eq!(
+            render_lists(&lists, Format::Tsv),
+            "\t\tToday\tlist\t\nGAR\tP1\tGarden\tproject\tHome\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render_lists(&lists, Format::Json)).unwrap();
+        assert_eq!(json[1]["kind"], "project");
+        assert_eq!(json[0]["area"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_render_tags_and_count() {
+        let tags = vec![TagInfo {
+            name: "errand".to_string(),
+            parent: Some("places".to_string()),
+            count: 2,
+        }];
+        assert_eq!(render_tags(&tags, Format::Tsv), "errand\tplaces\t2\n");
+        assert_eq!(render_tags(&[], Format::Json), "[]\n");
+        assert_eq!(render_count("Today", 3, Format::Tsv), "Today\t3\n");
+        assert_eq!(
+            render_count("Today", 3, Format::Json),
+            "{\n  \"list\": \"Today\",\n  \"count\": 3\n}\n"
+        );
+    }
+}
@@ -0,0 +1,571 @@
+// Filter expressions and sort keys for `thingy ls --where ... --sort ...`.
+//
+//     tag:errand and not tag:in-progress and due<3d
+//     (list:today or project:website) and time<=30m
+//     not has:tags or name:"call mom"
+//
+// Predicates are `field:value` (exact tag, list or project; substring of
+// name or notes; `has:` tests a field is present) or `field<op>value`
+// comparisons on dates (`due`, `when`, `created`, `completed`) and time
+// estimates (`time`).  Dates accept anything `quickadd::parse_date` does,
+// plus bare offsets like `3d`.  Combine with `and`, `or`, `not` and
+// parentheses; `and` binds tighter than `or`.
+
+use std::cmp::Ordering;
+
+use chrono::NaiveDate;
+
+use crate::quickadd;
+use crate::todo::{parse_time_seconds, Todo};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Tag(String),
+    List(String),
+    Project(String),
+    NameContains(String),
+    NotesContains(String),
+    Has(Presence),
+    Date(DateField, Op, NaiveDate),
+    Time(Op, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Tags,
+    Due,
+    When,
+    Time,
+    Notes,
+    Project,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    When,
+    Created,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Op {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Gt => ordering == Ordering::Greater,
+        }
+    }
+}
+
+impl DateField {
+    fn of(self, todo: &Todo) -> Option<NaiveDate> {
+        match self {
+            DateField::Due => todo.deadline,
+            DateField::When => todo.when,
+            DateField::Created => todo.created,
+            DateField::Completed => todo.completed_on,
+        }
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(todo) && b.matches(todo),
+            Expr::Or(a, b) => a.matches(todo) || b.matches(todo),
+            Expr::Not(e) => !e.matches(todo),
+            Expr::Tag(tag) => todo.has_tag(tag),
+            Expr::List(list) => todo.list.eq_ignore_ascii_case(list),
+            Expr::Project(project) => todo
+                .project
+                .as_deref()
+                .is_some_and(|p| p.eq_ignore_ascii_case(project)),
+            Expr::NameContains(text) => todo.name.to_lowercase().contains(text),
+            Expr::NotesContains(text) => todo.notes.to_lowercase().contains(text),
+            Expr::Has(presence) => match presence {
+                Presence::Tags => !todo.tags.is_empty(),
+                Presence::Due => todo.deadline.is_some(),
+                Presence::When => todo.when.is_some(),
+                Presence::Time => todo.time_secs().is_some(),
+                Presence::Notes => !todo.notes.trim().is_empty(),
+                Presence::Project => todo.project.is_some(),
+            },
+            Expr::Date(field, op, date) => field
+                .of(todo)
+                .is_some_and(|d| op.holds(d.cmp(date))),
+            Expr::Time(op, secs) => todo.time_secs().is_some_and(|t| op.holds(t.cmp(secs))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    let flush = |current: &mut String, tokens: &mut Vec<Token>| {
+        if !current.is_empty() {
+            tokens.push(Token::Word(std::mem::take(current)));
+        }
+    };
+
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => current.push(c),
+            '(' | ')' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            c if c.is_whitespace() => flush(&mut current, &mut tokens),
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quote in query".to_string());
+    }
+    flush(&mut current, &mut tokens);
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    today: NaiveDate,
+    query: &'a str,
+}
+
+impl Parser<'_> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err(format!("Missing ')' in query '{}'", self.query));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) if ["and", "or"].contains(&word.to_lowercase().as_str()) => {
+                Err(format!("Expected a condition before '{}' in query '{}'", word, self.query))
+            }
+            Some(Token::Word(word)) => parse_predicate(&word, self.today),
+            Some(Token::Close) => Err(format!("Unexpected ')' in query '{}'", self.query)),
+            None => Err(format!("Query '{}' ends too soon", self.query)),
+        }
+    }
+}
+
+/// Parses a `--where` expression, resolving relative dates against `today`.
+pub fn parse(query: &str, today: NaiveDate) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(query)?,
+        pos: 0,
+        today,
+        query,
+    };
+    if parser.tokens.is_empty() {
+        return Err("Empty query".to_string());
+    }
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!(
+            "Unexpected {} in query '{}' (missing 'and' or 'or'?)",
+            match &parser.tokens[parser.pos] {
+                Token::Word(w) => format!("'{}'", w),
+                Token::Open => "'('".to_string(),
+                Token::Close => "')'".to_string(),
+            },
+            query
+        ));
+    }
+    Ok(expr)
+}
+
+fn parse_predicate(word: &str, today: NaiveDate) -> Result<Expr, String> {
+    if let Some((field, value)) = word.split_once(':') {
+        if value.is_empty() {
+            return Err(format!("Missing value in '{}'", word));
+        }
+        return match field.to_lowercase().as_str() {
+            "tag" => Ok(Expr::Tag(value.to_string())),
+            "list" => Ok(Expr::List(value.to_string())),
+            "project" => Ok(Expr::Project(value.to_string())),
+            "name" => Ok(Expr::NameContains(value.to_lowercase())),
+            "notes" => Ok(Expr::NotesContains(value.to_lowercase())),
+            "has" => parse_presence(value).map(Expr::Has),
+            _ => Err(format!(
+                "Unknown field '{}' (use tag, list, project, name, notes or has)",
+                field
+            )),
+        };
+    }
+
+    let Some(op_start) = word.find(['<', '>', '=']) else {
+        return Err(format!("Expected a condition like tag:x or due<3d, got '{}'", word));
+    };
+    let (field, rest) = word.split_at(op_start);
+    let (op, value) = if let Some(value) = rest.strip_prefix("<=") {
+        (Op::Le, value)
+    } else if let Some(value) = rest.strip_prefix(">=") {
+        (Op::Ge, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Op::Lt, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Op::Gt, value)
+    } else {
+        (Op::Eq, rest.trim_start_matches('='))
+    };
+    if value.is_empty() {
+        return Err(format!("Missing value in '{}'", word));
+    }
+
+    let date_field = match field.to_lowercase().as_str() {
+        "time" => {
+            let secs = parse_time_seconds(value)
+                .ok_or_else(|| format!("Invalid time '{}' (e.g. 30m, 2h)", value))?;
+            return Ok(Expr::Time(op, secs));
+        }
+        "due" | "deadline" => DateField::Due,
+        "when" => DateField::When,
+        "created" => DateField::Created,
+        "completed" => DateField::Completed,
+        _ => {
+            return Err(format!(
+                "Unknown field '{}' (use due, when, created, completed or time)",
+                field
+            ))
+        }
+    };
+    Ok(Expr::Date(date_field, op, parse_query_date(value, today)?))
+}
+
+fn parse_presence(value: &str) -> Result<Presence, String> {
+    match value.to_lowercase().as_str() {
+        "tags" | "tag" => Ok(Presence::Tags),
+        "due" | "deadline" => Ok(Presence::Due),
+        "when" => Ok(Presence::When),
+        "time" => Ok(Presence::Time),
+        "notes" => Ok(Presence::Notes),
+        "project" => Ok(Presence::Project),
+        _ => Err(format!(
+            "Unknown has:{} (use tags, due, when, time, notes or project)",
+            value
+        )),
+    }
+}
+
+/// Like `quickadd::parse_date`, but `3d` means `+3d`, as in `due<3d`.
+fn parse_query_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    if value.starts_with(|c: char| c.is_ascii_digit()) && value.ends_with(['d', 'w']) {
+        return quickadd::parse_date(&format!("+{}", value), today)
+            .map_err(|e| e.replacen(&format!("'+{}'", value), &format!("'{}'", value), 1));
+    }
+    quickadd::parse_date(value, today)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    Time,
+    Due,
+    When,
+    Created,
+    Completed,
+    List,
+    Project,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+/// Parses `--sort` keys, e.g. `time,name` or `-due`.
+pub fn parse_sort(spec: &str) -> Result<Vec<SortKey>, String> {
+    spec.split(',')
+        .map(|key| key.trim())
+        .filter(|key| !key.is_empty())
+        .map(|key| {
+            let (descending, name) = match key.strip_prefix('-') {
+                Some(name) => (true, name),
+                None => (false, key),
+            };
+            let field = match name.to_lowercase().as_str() {
+                "name" => SortField::Name,
+                "time" => SortField::Time,
+                "due" | "deadline" => SortField::Due,
+                "when" => SortField::When,
+                "created" => SortField::Created,
+                "completed" => SortField::Completed,
+                "list" => SortField::List,
+                "project" => SortField::Project,
+                _ => {
+                    return Err(format!(
+                        "Unknown sort key '{}' (use name, time, due, when, created, completed, list or project)",
+                        name
+                    ))
+                }
+            };
+            Ok(SortKey { field, descending })
+        })
+        .collect()
+}
+
+/// Compares optional values with missing ones last, whatever the direction.
+fn cmp_present<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Stable sort by `keys` in order; ties keep list order.
+pub fn sort_todos(todos: &mut [&Todo], keys: &[SortKey]) {
+    todos.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| {
+                let d = key.descending;
+                match key.field {
+                    SortField::Name => cmp_present(
+                        Some(a.name.to_lowercase()),
+                        Some(b.name.to_lowercase()),
+                        d,
+                    ),
+                    SortField::Time => cmp_present(a.time_secs(), b.time_secs(), d),
+                    SortField::Due => cmp_present(a.deadline, b.deadline, d),
+                    SortField::When => cmp_present(a.when, b.when, d),
+                    SortField::Created => cmp_present(a.created, b.created, d),
+                    SortField::Completed => cmp_present(a.completed_on, b.completed_on, d),
+                    SortField::List => cmp_present(
+                        Some(a.list.to_lowercase()),
+                        Some(b.list.to_lowercase()),
+                        d,
+                    ),
+                    SortField::Project => cmp_present(
+                        a.project.as_ref().map(|p| p.to_lowercase()),
+                        b.project.as_ref().map(|p| p.to_lowercase()),
+                        d,
+                    ),
+                }
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::parse_tag_names;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    fn todo(name: &str, tags: &str) -> Todo {
+        Todo {
+            name: name.to_string(),
+            tags: parse_tag_names(tags),
+            list: "Today".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn matches(query: &str, todo: &Todo) -> bool {
+        parse(query, today()).unwrap().matches(todo)
+    }
+
+    #[test]
+    fn test_tag_is_exact() {
+        let t = todo("Buy milk", "errand, not-in-progress");
+        assert!(matches("tag:errand", &t));
+        assert!(!matches("tag:ERRAND", &t));
+        assert!(!matches("tag:in-progress", &t));
+        assert!(!matches("tag:err", &t));
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let t = todo("Buy milk", "errand");
+        assert!(matches("tag:errand and not tag:in-progress", &t));
+        assert!(matches("tag:home or tag:errand", &t));
+        assert!(!matches("tag:home or tag:work", &t));
+        assert!(matches("not not tag:errand", &t));
+        assert!(matches("TAG:errand AND NOT tag:home", &t));
+    }
+
+    #[test]
+    fn test_precedence_and_parentheses() {
+        let t = todo("Buy milk", "errand");
+        // and binds tighter: tag:home or (tag:errand and tag:work)
+        assert!(!matches("tag:home or tag:errand and tag:work", &t));
+        assert!(matches("(tag:home or tag:errand) and not tag:work", &t));
+        assert!(!matches("not (tag:errand or tag:home)", &t));
+    }
+
+    #[test]
+    fn test_text_fields() {
+        let mut t = todo("Call Mom", "");
+        t.notes = "Ask about Sunday lunch".to_string();
+        t.project = Some("Family".to_string());
+        assert!(matches("name:mom", &t));
+        assert!(matches("name:\"call mom\"", &t));
+        assert!(matches("notes:sunday", &t));
+        assert!(matches("project:family and list:today", &t));
+        assert!(!matches("project:work", &t));
+        assert!(!matches("list:inbox", &t));
+    }
+
+    #[test]
+    fn test_presence() {
+        let mut t = todo("Stretch", "15m");
+        t.deadline = date(2026, 10, 20);
+        assert!(matches("has:tags and has:time and has:due", &t));
+        assert!(!matches("has:when or has:notes or has:project", &t));
+        assert!(matches("not has:tags", &todo("Untagged", "")));
+    }
+
+    #[test]
+    fn test_dates() {
+        let mut t = todo("Report", "");
+        t.deadline = date(2026, 10, 19);
+        t.created = date(2026, 10, 1);
+        assert!(matches("due<3d", &t));
+        assert!(!matches("due<2d", &t));
+        assert!(matches("due<=+2d", &t));
+        assert!(matches("due=2026-10-19", &t));
+        assert!(matches("deadline>today", &t));
+        assert!(matches("created<2026-10-02 and created>=2026-10-01", &t));
+        // Missing dates never match a comparison:
+        assert!(!matches("when<2099-01-01", &t));
+        assert!(!matches("when>2000-01-01", &t));
+    }
+
+    #[test]
+    fn test_time() {
+        let t = todo("Stretch", "15m");
+        assert!(matches("time<=15m", &t));
+        assert!(matches("time<1h", &t));
+        assert!(!matches("time>30m", &t));
+        assert!(!matches("time<1h", &todo("No estimate", "")));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for query in [
+            "",
+            "tag:",
+            "colour:red",
+            "tag:a and",
+            "and tag:a",
+            "tag:a tag:b",
+            "(tag:a",
+            "tag:a)",
+            "due<",
+            "due<soon",
+            "time<fast",
+            "due<99999999999d",
+            "due<3000000000000000000w",
+            "time<99999999999999999d",
+            "size>3",
+            "has:wings",
+            "name:\"unterminated",
+            "errand",
+        ] {
+            assert!(parse(query, today()).is_err(), "'{}' should not parse", query);
+        }
+    }
+
+    #[test]
+    fn test_parse_sort() {
+        assert_eq!(
+            parse_sort("time,-name").unwrap(),
+            vec![
+                SortKey { field: SortField::Time, descending: false },
+                SortKey { field: SortField::Name, descending: true },
+            ]
+        );
+        assert!(parse_sort("colour").is_err());
+    }
+
+    #[test]
+    fn test_sort_todos() {
+        let quick = todo("b quick", "5m");
+        let slow = todo("a slow", "1h");
+        let unknown = todo("c unknown", "");
+        let also_quick = todo("a also quick", "5m");
+        let mut todos = vec![&unknown, &slow, &quick, &also_quick];
+
+        sort_todos(&mut todos, &parse_sort("time,name").unwrap());
+        let names: Vec<&str> = todos.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["a also quick", "b quick", "a slow", "c unknown"]);
+
+        // Descending still puts missing values last:
+        sort_todos(&mut todos, &parse_sort("-time").unwrap());
+        let names: Vec<&str> = todos.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["a slow", "a also quick", "b quick", "c unknown"]);
+    }
+}
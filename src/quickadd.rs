@@ -0,0 +1,328 @@
+// Quick-add syntax shared by `thingy add` and interactive mode:
+//
+//     Buy milk #errand @tomorrow !2026-11-01 -- notes here
+//
+// `#tag` adds a tag, `@when` schedules the todo, `!date` sets a deadline
+// and everything after `--` becomes the notes.  `--checklist "a;b;c"`
+// adds checklist items.  A leading backslash keeps a word literal, so
+// `\#1` is part of the name rather than a tag.
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+use crate::todo::{NewTodo, When};
+
+/// Parses quick-add words into a new todo; relative dates are taken
+/// from `today`.
+pub fn parse(args: &[String], today: NaiveDate) -> Result<NewTodo, String> {
+    let mut todo = NewTodo::default();
+    let mut name_words: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let word = args[i].as_str();
+        i += 1;
+
+        if word == "--" {
+            todo.notes = args[i..].join(" ");
+            break;
+        } else if word == "--checklist" {
+            let Some(items) = args.get(i) else {
+                return Err("--checklist requires a list of items, e.g. \"a;b;c\"".to_string());
+            };
+            i += 1;
+            todo.checklist.extend(parse_checklist(items)?);
+        } else if let Some(items) = word.strip_prefix("--checklist=") {
+            todo.checklist.extend(parse_checklist(items)?);
+        } else if let Some(literal) = word.strip_prefix('\\').filter(|w| !w.is_empty()) {
+            name_words.push(literal);
+        } else if let Some(tag) = word.strip_prefix('#').filter(|w| !w.is_empty()) {
+            if !todo.tags.iter().any(|t| t == tag) {
+                todo.tags.push(tag.to_string());
+            }
+        } else if let Some(when) = word.strip_prefix('@').filter(|w| !w.is_empty()) {
+            if todo.when.is_some() {
+                return Err(format!("More than one @when given ('{}')", word));
+            }
+            todo.when = Some(parse_when(when, today)?);
+        } else if let Some(deadline) = word.strip_prefix('!').filter(|w| !w.is_empty()) {
+            if todo.deadline.is_some() {
+                return Err(format!("More than one !deadline given ('{}')", word));
+            }
+            todo.deadline = Some(parse_date(deadline, today)?);
+        } else {
+            name_words.push(word);
+        }
+    }
+
+    todo.name = name_words.join(" ");
+    if todo.name.trim().is_empty() {
+        return Err("Todo text is empty".to_string());
+    }
+    Ok(todo)
+}
+
+/// Like `parse`, for a line typed at a prompt.  Double quotes group
+/// words, as a shell would.
+pub fn parse_line(line: &str, today: NaiveDate) -> Result<NewTodo, String> {
+    parse(&split_words(line)?, today)
+}
+
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut in_quotes = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err("Unterminated quote".to_string());
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+fn parse_checklist(items: &str) -> Result<Vec<String>, String> {
+    let items: Vec<String> = items
+        .split(';')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect();
+    if items.is_empty() {
+        return Err("--checklist has no items".to_string());
+    }
+    Ok(items)
+}
+
+fn parse_when(s: &str, today: NaiveDate) -> Result<When, String> {
+    match s.to_lowercase().as_str() {
+        "someday" => Ok(When::Someday),
+        "anytime" => Ok(When::Anytime),
+        _ => parse_date(s, today).map(When::Date),
+    }
+}
+
+/// Accepts `YYYY-MM-DD`, `today`, `tomorrow`, a weekday (the next one
+/// after today) or an offset such as `+3d` or `+2w`.
+pub fn parse_date(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let lower = s.to_lowercase();
+    let invalid = || {
+        format!(
+            "Unrecognized date '{}' (use YYYY-MM-DD, today, tomorrow, a weekday or +3d)",
+            s
+        )
+    };
+
+    match lower.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Days::new(1)),
+        _ => {}
+    }
+
+    if let Ok(weekday) = lower.parse::<Weekday>() {
+        let ahead = (weekday.num_days_from_monday() + 7
+            - today.weekday().num_days_from_monday())
+            % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return Ok(today + Days::new(ahead as u64));
+    }
+
+    if let Some(offset) = lower.strip_prefix('+') {
+        let Some((unit_start, unit)) = offset.char_indices().last() else {
+            return Err(invalid());
+        };
+        let count: u64 = offset[..unit_start].parse().map_err(|_| invalid())?;
+        let days = match unit {
+            'd' => Some(count),
+            'w' => count.checked_mul(7),
+            _ => return Err(invalid()),
+        };
+        return days
+            .and_then(|days| today.checked_add_days(Days::new(days)))
+            .ok_or_else(|| format!("Date '{}' is too far off", s));
+    }
+
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Saturday.
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn words(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    fn parse_words(line: &str) -> Result<NewTodo, String> {
+        parse(&words(line), today())
+    }
+
+    #[test]
+    fn test_plain_name() {
+        let todo = parse_words("Buy milk").unwrap();
+        assert_eq!(todo, NewTodo::new("Buy milk"));
+    }
+
+    #[test]
+    fn test_full_syntax() {
+        let todo = parse_words("Buy milk #errand @tomorrow !2026-11-01 -- notes here").unwrap();
+        assert_eq!(todo.name, "Buy milk");
+        assert_eq!(todo.tags, vec!["errand"]);
+        assert_eq!(todo.when, Some(When::Date(date(2026, 10, 18))));
+        assert_eq!(todo.deadline, Some(date(2026, 11, 1)));
+        assert_eq!(todo.notes, "notes here");
+    }
+
+    #[test]
+    fn test_markers_anywhere() {
+        let todo = parse_words("#home Fix #diy the @someday sink").unwrap();
+        assert_eq!(todo.name, "Fix the sink");
+        assert_eq!(todo.tags, vec!["home", "diy"]);
+        assert_eq!(todo.when, Some(When::Someday));
+    }
+
+    #[test]
+    fn test_duplicate_tags_collapse() {
+        let todo = parse_words("Call mom #phone #phone").unwrap();
+        assert_eq!(todo.tags, vec!["phone"]);
+    }
+
+    #[test]
+    fn test_notes_keep_markers() {
+        let todo = parse_words("Deploy -- see #123 @ops !now").unwrap();
+        assert_eq!(todo.name, "Deploy");
+        assert!(todo.tags.is_empty());
+        assert_eq!(todo.notes, "see #123 @ops !now");
+    }
+
+    #[test]
+    fn test_escaped_and_bare_markers() {
+        let todo = parse_words("Fix bug \\#123 for \\@bob ! # @").unwrap();
+        assert_eq!(todo.name, "Fix bug #123 for @bob ! # @");
+        assert!(todo.tags.is_empty());
+        assert_eq!(todo.when, None);
+    }
+
+    #[test]
+    fn test_checklist() {
+        let args = vec![
+            "Pack".to_string(),
+            "--checklist".to_string(),
+            "socks; toothbrush;;charger ".to_string(),
+        ];
+        let todo = parse(&args, today()).unwrap();
+        assert_eq!(todo.name, "Pack");
+        assert_eq!(todo.checklist, vec!["socks", "toothbrush", "charger"]);
+
+        let todo = parse_words("Pack --checklist=a;b").unwrap();
+        assert_eq!(todo.checklist, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_checklist_errors() {
+        assert!(parse_words("Pack --checklist").is_err());
+        assert!(parse_words("Pack --checklist ;;").is_err());
+    }
+
+    #[test]
+    fn test_empty_name() {
+        assert!(parse_words("").is_err());
+        assert!(parse_words("#errand @today").is_err());
+        assert!(parse_words("-- just notes").is_err());
+    }
+
+    #[test]
+    fn test_repeated_when_or_deadline() {
+        assert!(parse_words("Buy milk @today @tomorrow").is_err());
+        assert!(parse_words("Buy milk !today !tomorrow").is_err());
+    }
+
+    #[test]
+    fn test_when_values() {
+        assert_eq!(parse_words("x @today").unwrap().when, Some(When::Date(today())));
+        assert_eq!(parse_words("x @Anytime").unwrap().when, Some(When::Anytime));
+        assert_eq!(parse_words("x @someday").unwrap().when, Some(When::Someday));
+        assert!(parse_words("x @later").is_err());
+        assert!(parse_words("x !someday").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("today", today()), Ok(today()));
+        assert_eq!(parse_date("Tomorrow", today()), Ok(date(2026, 10, 18)));
+        assert_eq!(parse_date("2026-12-31", today()), Ok(date(2026, 12, 31)));
+        assert_eq!(parse_date("+3d", today()), Ok(date(2026, 10, 20)));
+        assert_eq!(parse_date("+2w", today()), Ok(date(2026, 10, 31)));
+    }
+
+    #[test]
+    fn test_parse_date_weekdays() {
+        assert_eq!(parse_date("mon", today()), Ok(date(2026, 10, 19)));
+        assert_eq!(parse_date("friday", today()), Ok(date(2026, 10, 23)));
+        // The same weekday means next week, not today:
+        assert_eq!(parse_date("sat", today()), Ok(date(2026, 10, 24)));
+    }
+
+    #[test]
+    fn test_parse_date_invalid() {
+        for s in ["", "2026-13-01", "17/10/2026", "+d", "+3m", "+-1d", "soon", "+", "+3é"] {
+            assert!(parse_date(s, today()).is_err(), "{} should not parse", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_date_too_far() {
+        for s in ["+99999999999999d", "+3000000000000000000w", "+99999999999999999999d"] {
+            assert!(parse_date(s, today()).is_err(), "{} should not parse", s);
+        }
+        assert!(parse_words("x @+3é").is_err());
+        assert!(parse_words("x !+99999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_parse_line_quotes() {
+        let todo = parse_line("Pack bag --checklist \"passport; phone charger\" #travel", today())
+            .unwrap();
+        assert_eq!(todo.name, "Pack bag");
+        assert_eq!(todo.checklist, vec!["passport", "phone charger"]);
+        assert_eq!(todo.tags, vec!["travel"]);
+
+        let todo = parse_line("  \"Read 'Dune'\"   -- \"a  b\"", today()).unwrap();
+        assert_eq!(todo.name, "Read 'Dune'");
+        assert_eq!(todo.notes, "a  b");
+    }
+
+    #[test]
+    fn test_parse_line_unterminated_quote() {
+        assert!(parse_line("Buy \"milk", today()).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+// Wire format between the generated AppleScript and Rust.
+//
+// Each record is a list of text fields joined by the ASCII unit separator
+// and terminated by the ASCII record separator.  Inside a field, a
+// backslash is written as `\\`, a unit separator as `\U` and a record
+// separator as `\R`, so names containing pipes, newlines or even the
+// separators themselves survive the round trip.
+
+use chrono::NaiveDate;
+
+use crate::registry::RegistryStore;
+use crate::todo::{parse_tag_names, Todo};
+
+pub const UNIT_SEP: char = '\u{1f}';
+pub const RECORD_SEP: char = '\u{1e}';
+
+/// AppleScript handlers that produce the format above.  Prepend to a
+/// script and call `my encodeRecord({field, ...})` for each record.
+pub const APPLESCRIPT_HANDLERS: &str = r#"
+on replaceText(sourceText, searchText, replacementText)
+    set oldDelimiters to AppleScript's text item delimiters
+    set AppleScript's text item delimiters to searchText
+    set textParts to text items of sourceText
+    set AppleScript's text item delimiters to replacementText
+    set resultText to textParts as text
+    set AppleScript's text item delimiters to oldDelimiters
+    return resultText
+end replaceText
+
+on escapeField(fieldValue)
+    if fieldValue is missing value then return ""
+    set fieldText to fieldValue as text
+    set fieldText to my replaceText(fieldText, "\\", "\\\\")
+    set fieldText to my replaceText(fieldText, character id 31, "\\U")
+    set fieldText to my replaceText(fieldText, character id 30, "\\R")
+    return fieldText
+end escapeField
+
+on isoDate(theDate)
+    if theDate is missing value then return ""
+    set yearText to (year of theDate) as text
+    set monthText to text -2 thru -1 of ("0" & ((month of theDate) as integer))
+    set dayText to text -2 thru -1 of ("0" & (day of theDate))
+    return yearText & "-" & monthText & "-" & dayText
+end isoDate
+
+on projectName(theTodo)
+    tell application "Things3"
+        set theProject to project of theTodo
+        if theProject is missing value then return ""
+        return name of theProject
+    end tell
+end projectName
+
+on encodeRecord(fieldList)
+    set escapedFields to {}
+    repeat with fieldValue in fieldList
+        set end of escapedFields to my escapeField(contents of fieldValue)
+    end repeat
+    set oldDelimiters to AppleScript's text item delimiters
+    set AppleScript's text item delimiters to character id 31
+    set recordText to escapedFields as text
+    set AppleScript's text item delimiters to oldDelimiters
+    return recordText & (character id 30)
+end encodeRecord
+"#;
+
+fn unescape_field(field: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('U') => out.push(UNIT_SEP),
+            Some('R') => out.push(RECORD_SEP),
+            Some(other) => return Err(format!("Invalid escape '\\{}' in field '{}'", other, field)),
+            None => return Err(format!("Dangling escape at end of field '{}'", field)),
+        }
+    }
+    Ok(out)
+}
+
+/// Fields of a todo record, as produced by `TODO_FIELDS`.
+const TODO_FIELD_COUNT: usize = 10;
+
+/// AppleScript list expression encoding the to do in `todo`.  Dates are
+/// ISO 8601 (`YYYY-MM-DD`) or empty.
+pub const TODO_FIELDS: &str = "{id of todo, statusFlag, name of todo, tag names of todo, \
+my isoDate(activation date of todo), my isoDate(due date of todo), \
+my isoDate(creation date of todo), my isoDate(completion date of todo), \
+my projectName(todo), notes of todo}";
+
+/// Splits script output into records of unescaped fields.  Anything after
+/// the last record separator must be whitespace (osascript adds a newline).
+pub fn parse_records(output: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut chunks: Vec<&str> = output.split(RECORD_SEP).collect();
+    let trailing = chunks.pop().unwrap_or("");
+    if !trailing.trim().is_empty() {
+        return Err(format!("Truncated record in script output: '{}'", trailing));
+    }
+
+    chunks
+        .into_iter()
+        .map(|chunk| chunk.split(UNIT_SEP).map(unescape_field).collect())
+        .collect()
+}
+
+fn parse_date(field: &str) -> Result<Option<NaiveDate>, String> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(field, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|e| format!("Invalid date '{}': {}", field, e))
+}
+
+/// Parses todo records (see `TODO_FIELDS`) from `list_name` into todos,
+/// numbered and with identifiers from `registry` assigned in output order.
+pub fn parse_todos(
+    output: &str,
+    list_name: &str,
+    registry: &RegistryStore,
+) -> Result<Vec<Todo>, String> {
+    let mut todos = Vec::new();
+    for (idx, fields) in parse_records(output)?.into_iter().enumerate() {
+        let [id, status, name, tags, when, deadline, created, completed_on, project, notes]:
+            [String; TODO_FIELD_COUNT] = fields.try_into().map_err(|f: Vec<String>| {
+                format!("Expected {} fields per todo, got {}", TODO_FIELD_COUNT, f.len())
+            })?;
+        todos.push(Todo {
+            id,
+            name,
+            tags: parse_tag_names(&tags),
+            is_completed: status == "completed",
+            index: idx + 1,
+            list: list_name.to_string(),
+            when: parse_date(&when)?,
+            deadline: parse_date(&deadline)?,
+            created: parse_date(&created)?,
+            completed_on: parse_date(&completed_on)?,
+            project: if project.is_empty() { None } else { Some(project) },
+            notes,
+            ..Default::default()
+        });
+    }
+    crate::identifiers::assign_identifiers(&mut todos, registry);
+    Ok(todos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mirrors the AppleScript `encodeRecord` handler.
+    fn encode_record(fields: &[&str]) -> String {
+        let escaped: Vec<String> = fields
+            .iter()
+            .map(|f| {
+                f.replace('\\', "\\\\")
+                    .replace(UNIT_SEP, "\\U")
+                    .replace(RECORD_SEP, "\\R")
+            })
+            .collect();
+        format!("{}{}", escaped.join(&UNIT_SEP.to_string()), RECORD_SEP)
+    }
+
+    fn todo_record(id: &str, status: &str, name: &str, tags: &str) -> String {
+        encode_record(&[id, status, name, tags, "", "", "", "", "", ""])
+    }
+
+    fn round_trip(name: &str) -> String {
+        let output = todo_record("id-1", "open", name, "") + "\n";
+        parse_todos(&output, "Today", &RegistryStore::in_memory()).unwrap().remove(0).name
+    }
+
+    #[test]
+    fn test_empty_output() {
+        assert!(parse_records("").unwrap().is_empty());
+        assert!(parse_records("\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_multiple_records() {
+        let output = encode_record(&["a", "b"]) + &encode_record(&["c", ""]) + "\n";
+        assert_eq!(
+            parse_records(&output).unwrap(),
+            vec![vec!["a", "b"], vec!["c", ""]]
+        );
+    }
+
+    #[test]
+    fn test_hostile_names() {
+        for name in [
+            "Fix a|b pipeline",
+            "Line one\nLine two",
+            "Trailing newline\n",
+            "  padded  ",
+            "C:\\path\\to\\file",
+            "ends with backslash\\",
+            "literal \\U and \\R",
+            "unit\u{1f}separator",
+            "record\u{1e}separator",
+            "quote \" and comma, here",
+            "日本語 ✓",
+            "",
+        ] {
+            assert_eq!(round_trip(name), name);
+        }
+    }
+
+    #[test]
+    fn test_parse_todos_fields() {
+        let output = encode_record(&[
+            "abc", "completed", "Buy milk", "errand, home",
+            "2026-10-17", "2026-11-01", "2026-10-01", "2026-10-17",
+            "Errands", "2%\nor whole",
+        ]) + &todo_record("def", "open", "Call mom", "");
+        let todos = parse_todos(&output, "Today", &RegistryStore::in_memory()).unwrap();
+
+        assert_eq!(todos[0].id, "abc");
+        assert!(todos[0].is_completed);
+        assert_eq!(todos[0].tags, vec!["errand", "home"]);
+        assert_eq!(todos[0].identifier, "BUY");
+        assert_eq!(todos[0].list, "Today");
+        assert_eq!(todos[0].when, NaiveDate::from_ymd_opt(2026, 10, 17));
+        assert_eq!(todos[0].deadline, NaiveDate::from_ymd_opt(2026, 11, 1));
+        assert_eq!(todos[0].created, NaiveDate::from_ymd_opt(2026, 10, 1));
+        assert_eq!(todos[0].completed_on, NaiveDate::from_ymd_opt(2026, 10, 17));
+        assert_eq!(todos[0].project.as_deref(), Some("Errands"));
+        assert_eq!(todos[0].notes, "2%\nor whole");
+        assert_eq!(todos[1].project, None);
+        assert_eq!(todos[1].index, 2);
+        assert!(!todos[1].is_completed);
+        assert_eq!(todos[1].deadline, None);
+    }
+
+    #[test]
+    fn test_invalid_date() {
+        let output = encode_record(&["abc", "open", "Buy milk", "", "17/10/2026", "", "", "", "", ""]);
+        assert!(parse_todos(&output, "Today", &RegistryStore::in_memory()).is_err());
+    }
+
+    #[test]
+    fn test_wrong_field_count() {
+        let output = encode_record(&["abc", "open", "Buy milk"]);
+        assert!(parse_todos(&output, "Today", &RegistryStore::in_memory()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_escape() {
+        assert!(parse_records("bad \\x escape\u{1e}").is_err());
+        assert!(parse_records("dangling \\\u{1e}").is_err());
+    }
+
+    #[test]
+    fn test_truncated_output() {
+        let output = encode_record(&["a"]) + "partial";
+        assert!(parse_records(&output).is_err());
+    }
+}
@@ -0,0 +1,351 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::identifiers::resolve_collision;
+use crate::todo::Todo;
+
+/// Days a todo can go unseen (completed, deleted, or just in a list
+/// nobody looked at) before its identifier may be given to another todo.
+pub const GRACE_PERIOD_DAYS: u64 = 7;
+
+/// Identifiers handed out so far, keyed by todo id, so a todo keeps its
+/// identifier however the lists around it change.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Registry {
+    /// The identifier length the entries were made with.
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    identifier: String,
+    last_seen: NaiveDate,
+}
+
+/// Whether `todo` is neither completed nor canceled.
+fn is_open(todo: &Todo) -> bool {
+    !todo.is_completed && todo.completed_on.is_none()
+}
+
+impl Registry {
+    /// Gives each of `todos` its registered identifier, registering new
+    /// open todos under the first free identifier derived from their name.
+    /// Completed and canceled todos don't use identifiers up: unless they
+    /// kept one from when they were open, they get a free one for now.
+    pub fn assign(&mut self, todos: &mut [Todo], width: usize, today: NaiveDate) {
+        if self.width != width {
+            self.entries.clear();
+            self.width = width;
+        }
+
+        let mut new_todos = Vec::new();
+        for todo in todos.iter_mut() {
+            match self.entries.get_mut(&todo.id) {
+                Some(entry) => {
+                    if is_open(todo) {
+                        entry.last_seen = today;
+                    }
+                    todo.identifier = entry.identifier.clone();
+                }
+                None => new_todos.push(todo),
+            }
+        }
+
+        // Only todos that weren't just seen can have expired:
+        let cutoff = today
+            .checked_sub_days(Days::new(GRACE_PERIOD_DAYS))
+            .unwrap_or(NaiveDate::MIN);
+        self.entries.retain(|_, entry| entry.last_seen >= cutoff);
+
+        let mut taken: HashSet<String> =
+            self.entries.values().map(|e| e.identifier.clone()).collect();
+        for todo in new_todos {
+            let base_id = Todo::generate_base_identifier(&todo.name, width);
+            let identifier = (0..=999)
+                .map(|n| match n {
+                    0 => base_id.clone(),
+                    n => resolve_collision(&base_id, n),
+                })
+                .find(|candidate| !taken.contains(candidate))
+                .unwrap_or_else(|| panic!("Too many collisions for identifier {}", base_id));
+
+            taken.insert(identifier.clone());
+            todo.identifier = identifier.clone();
+            if !is_open(todo) {
+                continue;
+            }
+            self.entries.insert(
+                todo.id.clone(),
+                Entry {
+                    identifier,
+                    last_seen: today,
+                },
+            );
+        }
+    }
+}
+
+fn registry_path() -> PathBuf {
+    if let Ok(path) = env::var("THINGY_REGISTRY") {
+        return PathBuf::from(path);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".thingy-identifiers.json")
+}
+
+fn load(path: &Path) -> Result<Registry, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Error parsing {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Registry::default()),
+        Err(e) => Err(format!("Error reading {}: {}", path.display(), e)),
+    }
+}
+
+fn save(path: &Path, registry: &Registry) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(registry)
+        .map_err(|e| format!("Error serializing identifiers: {}", e))?;
+    write_replacing(path, &json).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+/// Writes `contents` to a file next to `path`, then renames it over
+/// `path`, so a run that dies halfway leaves the old file whole.
+pub(crate) fn write_replacing(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+/// Where a registry is kept: the registry file, or memory for a backend
+/// that keeps nothing on disk.  Clones share their registry.
+#[derive(Debug, Clone)]
+pub enum RegistryStore {
+    File(PathBuf),
+    Memory(Rc<RefCell<Registry>>),
+}
+
+impl RegistryStore {
+    /// The file at `$THINGY_REGISTRY`, else `~/.thingy-identifiers.json`.
+    pub fn from_env() -> Self {
+        RegistryStore::File(registry_path())
+    }
+
+    pub fn in_memory() -> Self {
+        RegistryStore::Memory(Rc::default())
+    }
+
+    /// Runs `f` on the registry, saving any changes.  A registry that
+    /// can't be read or written only costs stable identifiers, so
+    /// problems are reported as warnings; one that can't be read is left
+    /// alone, so it can be fixed.
+    pub fn update(&self, f: impl FnOnce(&mut Registry)) {
+        let path = match self {
+            RegistryStore::File(path) => path,
+            RegistryStore::Memory(registry) => return f(&mut registry.borrow_mut()),
+        };
+        let mut registry = match load(path) {
+            Ok(registry) => registry,
+            Err(error) => {
+                eprintln!("Warning: {}", error);
+                f(&mut Registry::default());
+                return;
+            }
+        };
+        let before = registry.clone();
+        f(&mut registry);
+        if registry != before {
+            if let Err(error) = save(path, &registry) {
+                eprintln!("Warning: {}", error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(n: u64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 1).unwrap() + Days::new(n)
+    }
+
+    fn todos(items: &[(&str, &str)]) -> Vec<Todo> {
+        items
+            .iter()
+            .map(|(id, name)| Todo {
+                id: id.to_string(),
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn assign(registry: &mut Registry, items: &[(&str, &str)], today: NaiveDate) -> Vec<String> {
+        let mut todos = todos(items);
+        registry.assign(&mut todos, 3, today);
+        todos.into_iter().map(|t| t.identifier).collect()
+    }
+
+    #[test]
+    fn test_first_assignment_matches_list_order() {
+        let mut registry = Registry::default();
+        let ids = assign(
+            &mut registry,
+            &[("a", "Hello world"), ("b", "Hello there"), ("c", "Call mom")],
+            day(0),
+        );
+        assert_eq!(ids, vec!["HEL", "HE1", "CAL"]);
+    }
+
+    #[test]
+    fn test_identifiers_survive_completion() {
+        let mut registry = Registry::default();
+        assign(&mut registry, &[("a", "Hello world"), ("b", "Hello there")], day(0));
+
+        // "Hello world" is completed and drops out of the list:
+        let ids = assign(&mut registry, &[("b", "Hello there")], day(1));
+        assert_eq!(ids, vec!["HE1"]);
+    }
+
+    #[test]
+    fn test_new_todos_skip_handles_in_grace_period() {
+        let mut registry = Registry::default();
+        assign(&mut registry, &[("a", "Hello world"), ("b", "Hello there")], day(0));
+
+        let ids = assign(&mut registry, &[("b", "Hello there"), ("c", "Hello again")], day(3));
+        assert_eq!(ids, vec!["HE1", "HE2"]);
+    }
+
+    #[test]
+    fn test_handles_recycled_after_grace_period() {
+        let mut registry = Registry::default();
+        assign(&mut registry, &[("a", "Hello world"), ("b", "Hello there")], day(0));
+
+        let later = day(GRACE_PERIOD_DAYS + 1);
+        let ids = assign(&mut registry, &[("b", "Hello there"), ("c", "Hello again")], later);
+        assert_eq!(ids, vec!["HE1", "HEL"]);
+    }
+
+    #[test]
+    fn test_seen_todos_keep_handles_past_grace_period() {
+        let mut registry = Registry::default();
+        assign(&mut registry, &[("a", "Someday maybe")], day(0));
+
+        let ids = assign(&mut registry, &[("a", "Someday maybe")], day(30));
+        assert_eq!(ids, vec!["SOM"]);
+    }
+
+    #[test]
+    fn test_handles_unique_across_lists() {
+        let mut registry = Registry::default();
+        let inbox = assign(&mut registry, &[("a", "Buy milk")], day(0));
+        let today = assign(&mut registry, &[("b", "Buy bread")], day(0));
+        assert_eq!(inbox, vec!["BUY"]);
+        assert_eq!(today, vec!["BU1"]);
+
+        // Moving a todo to another list keeps its handle:
+        let today = assign(&mut registry, &[("a", "Buy milk"), ("b", "Buy bread")], day(1));
+        assert_eq!(today, vec!["BUY", "BU1"]);
+    }
+
+    #[test]
+    fn test_churn() {
+        let mut registry = Registry::default();
+        let mut live: Vec<(String, String)> = Vec::new();
+        for round in 0..50u64 {
+            // Complete the oldest todo and add two new ones each day:
+            if round % 2 == 0 && !live.is_empty() {
+                live.remove(0);
+            }
+            live.push((format!("t{}", round * 2), "Task".to_string()));
+            live.push((format!("t{}", round * 2 + 1), "Task".to_string()));
+
+            let items: Vec<(&str, &str)> =
+                live.iter().map(|(id, name)| (id.as_str(), name.as_str())).collect();
+            let before = registry.clone();
+            let ids = assign(&mut registry, &items, day(round));
+
+            let unique: HashSet<&String> = ids.iter().collect();
+            assert_eq!(unique.len(), ids.len(), "duplicate identifiers on day {}", round);
+            for (id, identifier) in items.iter().map(|(id, _)| id).zip(&ids) {
+                if let Some(entry) = before.entries.get(*id) {
+                    assert_eq!(&entry.identifier, identifier, "{} changed on day {}", id, round);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_todos_use_no_identifiers() {
+        let mut registry = Registry::default();
+        assign(&mut registry, &[("a", "Hello world")], day(0));
+
+        let mut logbook = todos(&[("a", "Hello world"), ("b", "Hello there"), ("c", "Call mom")]);
+        for todo in &mut logbook {
+            todo.is_completed = true;
+        }
+        registry.assign(&mut logbook, 3, day(1));
+        let ids: Vec<&str> = logbook.iter().map(|t| t.identifier.as_str()).collect();
+        assert_eq!(ids, vec!["HEL", "HE1", "CAL"]);
+
+        // Only the todo registered while open holds on to its identifier:
+        let ids = assign(&mut registry, &[("d", "Call dad"), ("e", "Hello again")], day(2));
+        assert_eq!(ids, vec!["CAL", "HE1"]);
+
+        // ...until its grace period runs out, however often it's seen:
+        registry.assign(&mut logbook, 3, day(GRACE_PERIOD_DAYS));
+        let ids = assign(&mut registry, &[("f", "Hello you")], day(GRACE_PERIOD_DAYS + 1));
+        assert_eq!(ids, vec!["HEL"]);
+    }
+
+    #[test]
+    fn test_write_replacing() {
+        let dir = std::env::temp_dir().join(format!("thingy-registry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("identifiers.json");
+        fs::write(&path, "old").unwrap();
+
+        write_replacing(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_keeps_identifiers() {
+        let path = std::env::temp_dir().join(format!("thingy-identifiers-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let store = RegistryStore::File(path.clone());
+        store.update(|registry| {
+            assign(registry, &[("a", "Hello world")], day(0));
+        });
+
+        let mut ids = Vec::new();
+        RegistryStore::File(path.clone()).update(|registry| {
+            ids = assign(registry, &[("b", "Hello there"), ("a", "Hello world")], day(1));
+        });
+        assert_eq!(ids, vec!["HE1", "HEL"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_width_change_starts_over() {
+        let mut registry = Registry::default();
+        assign(&mut registry, &[("a", "Hello world")], day(0));
+
+        let mut todos = todos(&[("a", "Hello world")]);
+        registry.assign(&mut todos, 4, day(1));
+        assert_eq!(todos[0].identifier, "HELL");
+    }
+}
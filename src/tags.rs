@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub parent: Option<String>,
+    /// Open todos carrying the tag.
+    pub count: usize,
+}
+
+/// Lines showing `tags` as a tree: children indented under their parent,
+/// siblings sorted by name.  Tags whose parent is missing are shown at
+/// the top level.
+pub fn tag_tree_lines(tags: &[TagInfo]) -> Vec<String> {
+    let names: HashSet<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+    let mut lines = Vec::new();
+    let mut shown = HashSet::new();
+
+    let mut roots: Vec<&TagInfo> = tags
+        .iter()
+        .filter(|t| t.parent.as_deref().is_none_or(|p| !names.contains(p)))
+        .collect();
+    roots.sort_by(|a, b| a.name.cmp(&b.name));
+    for root in roots {
+        push_subtree(root, tags, 0, &mut lines, &mut shown);
+    }
+    lines
+}
+
+fn push_subtree<'a>(
+    tag: &'a TagInfo,
+    tags: &'a [TagInfo],
+    depth: usize,
+    lines: &mut Vec<String>,
+    shown: &mut HashSet<&'a str>,
+) {
+    // Guards against a parent cycle:
+    if !shown.insert(tag.name.as_str()) {
+        return;
+    }
+    lines.push(format!("{}{} ({})", "  ".repeat(depth), tag.name, tag.count));
+
+    let mut children: Vec<&TagInfo> = tags
+        .iter()
+        .filter(|t| t.parent.as_deref() == Some(tag.name.as_str()))
+        .collect();
+    children.sort_by(|a, b| a.name.cmp(&b.name));
+    for child in children {
+        push_subtree(child, tags, depth + 1, lines, shown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, parent: Option<&str>, count: usize) -> TagInfo {
+        TagInfo {
+            name: name.to_string(),
+            parent: parent.map(|p| p.to_string()),
+            count,
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        let tags = vec![
+            tag("work", None, 5),
+            tag("meetings", Some("work"), 2),
+            tag("errand", None, 3),
+            tag("calls", Some("work"), 0),
+            tag("standup", Some("meetings"), 1),
+        ];
+        assert_eq!(
+            tag_tree_lines(&tags),
+            vec![
+                "errand (3)",
+                "work (5)",
+                "  calls (0)",
+                "  meetings (2)",
+                "    standup (1)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_parent_is_top_level() {
+        let tags = vec![tag("child", Some("gone"), 1)];
+        assert_eq!(tag_tree_lines(&tags), vec!["child (1)"]);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(tag_tree_lines(&[]).is_empty());
+    }
+}
@@ -0,0 +1,686 @@
+use chrono::{Datelike, NaiveDate};
+
+use crate::applescript::{run_applescript, FILTER_COMPLETED};
+use crate::backend::{Backend, Op};
+use crate::config;
+use crate::error::{Result, ThingyError};
+use crate::journal::Snapshot;
+use crate::lists::{self, ListInfo, ListKind};
+use crate::tags::TagInfo;
+use crate::things_url;
+use crate::records::{parse_records, parse_todos, APPLESCRIPT_HANDLERS, TODO_FIELDS};
+use crate::todo::{parse_tag_names, NewTodo, Todo, When};
+
+/// The Things3 backend, driven through `osascript`.
+pub struct ThingsBackend;
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// AppleScript handler building a date at midnight without going through
+/// locale-dependent date strings.  Use via `date_expr`.
+const DATE_HANDLER: &str = r#"
+on makeDate(yearNumber, monthNumber, dayNumber)
+    set theDate to current date
+    set day of theDate to 1
+    set year of theDate to yearNumber
+    set month of theDate to monthNumber
+    set day of theDate to dayNumber
+    set time of theDate to 0
+    return theDate
+end makeDate
+"#;
+
+fn date_expr(date: NaiveDate) -> String {
+    format!("(my makeDate({}, {}, {}))", date.year(), date.month(), date.day())
+}
+
+/// Builds a script that emits a record per to do in `todoList`, which
+/// `selection` must set up inside the Things3 tell block.
+fn fetch_script(selection: &str) -> String {
+    format!(
+        r#"{}
+tell application "Things3"
+{}
+    set output to ""
+    repeat with todo in todoList
+        if status of todo is completed then
+            set statusFlag to "completed"
+        else
+            set statusFlag to "open"
+        end if
+        set output to output & my encodeRecord({})
+    end repeat
+    return output
+end tell
+"#,
+        APPLESCRIPT_HANDLERS, selection, TODO_FIELDS
+    )
+}
+
+/// The one place tags get edited.  Things3 keeps a todo's tags as a
+/// comma-separated string; this splits it into a list so membership is
+/// exact ("work" doesn't match "homework"), creates any missing tags and
+/// returns the new tag string.
+const TAG_HANDLERS: &str = r#"
+on tagList(theTodo)
+    tell application "Things3"
+        set currentTags to tag names of theTodo
+    end tell
+    if currentTags is "" then return {}
+    set oldDelimiters to AppleScript's text item delimiters
+    set AppleScript's text item delimiters to ", "
+    set tagItems to text items of currentTags
+    set AppleScript's text item delimiters to oldDelimiters
+    return tagItems
+end tagList
+
+on editTags(theTodo, tagsToAdd, tagsToRemove)
+    set newTags to {}
+    repeat with tagItem in my tagList(theTodo)
+        set tagName to tagItem as text
+        if tagsToRemove does not contain tagName and newTags does not contain tagName then
+            set end of newTags to tagName
+        end if
+    end repeat
+    tell application "Things3"
+        repeat with tagItem in tagsToAdd
+            set tagName to tagItem as text
+            if not (exists tag tagName) then
+                make new tag with properties {name:tagName}
+            end if
+            if newTags does not contain tagName then
+                set end of newTags to tagName
+            end if
+        end repeat
+    end tell
+    set oldDelimiters to AppleScript's text item delimiters
+    set AppleScript's text item delimiters to ", "
+    set newTagText to newTags as text
+    set AppleScript's text item delimiters to oldDelimiters
+    tell application "Things3"
+        set tag names of theTodo to newTagText
+    end tell
+    return newTagText
+end editTags
+"#;
+
+/// Builds a script that selects the to do with Things id `id` as
+/// `todoVar`, then runs `body`.  `body` may use `TAG_HANDLERS`.
+fn todo_script(id: &str, todo_var: &str, body: &str) -> String {
+    format!(
+        r#"{}
+tell application "Things3"
+    set {} to to do id "{}"
+{}
+end tell
+"#,
+        TAG_HANDLERS, todo_var, escape(id), body
+    )
+}
+
+/// AppleScript setting `listToQuery` to the built-in list, project or area
+/// called `list_name`.
+fn select_list(list_name: &str) -> String {
+    if lists::is_built_in(list_name) {
+        return format!(r#"    set listToQuery to list "{}""#, escape(list_name));
+    }
+    format!(
+        r#"    if exists project "{name}" then
+        set listToQuery to project "{name}"
+    else
+        set listToQuery to area "{name}"
+    end if"#,
+        name = escape(list_name)
+    )
+}
+
+/// AppleScript moving `todo_var` to the built-in list, project or area
+/// called `list_name`.
+fn move_to_list(todo_var: &str, list_name: &str) -> String {
+    if lists::is_built_in(list_name) {
+        return format!(r#"    move {} to list "{}""#, todo_var, escape(list_name));
+    }
+    format!(
+        r#"    if exists project "{name}" then
+        set project of {todo} to project "{name}"
+    else
+        set area of {todo} to area "{name}"
+    end if"#,
+        todo = todo_var,
+        name = escape(list_name)
+    )
+}
+
+/// An AppleScript list literal of strings.
+fn applescript_list(items: &[&str]) -> String {
+    let quoted: Vec<String> = items.iter().map(|i| format!("\"{}\"", escape(i))).collect();
+    format!("{{{}}}", quoted.join(", "))
+}
+
+/// AppleScript statement adding `add` to and removing `remove` from the
+/// tags of `todo_var`.
+fn edit_tags_statement(todo_var: &str, add: &[&str], remove: &[&str]) -> String {
+    format!(
+        "    my editTags({}, {}, {})",
+        todo_var,
+        applescript_list(add),
+        applescript_list(remove)
+    )
+}
+
+/// AppleScript applying `op` to `todo_var`.
+fn op_statement(op: &Op, todo_var: &str) -> String {
+    match op {
+        Op::Complete { .. } => format!(
+            "{}\n    set status of {} to completed",
+            edit_tags_statement(todo_var, &[], &[&config::get().tags.in_progress]),
+            todo_var
+        ),
+        Op::Delete { .. } => format!("    delete {}", todo_var),
+        Op::Move { to_list, .. } => move_to_list(todo_var, to_list),
+        Op::Defer { .. } => format!("    schedule {} for (current date) + (1 * days)", todo_var),
+        Op::EditTags { add, remove, .. } => {
+            let add: Vec<&str> = add.iter().map(|t| t.as_str()).collect();
+            let remove: Vec<&str> = remove.iter().map(|t| t.as_str()).collect();
+            edit_tags_statement(todo_var, &add, &remove)
+        }
+        Op::Restore { before, .. } => restore_statements(todo_var, before),
+    }
+}
+
+/// AppleScript putting `todo_var` back as `before` describes.  Moving it
+/// to a list also brings it out of the Trash.  Dates are only ever set
+/// here, since no command clears them.
+fn restore_statements(todo_var: &str, before: &Snapshot) -> String {
+    let mut steps = vec![
+        move_to_list(todo_var, &before.list),
+        format!(
+            "    set status of {} to {}",
+            todo_var,
+            if before.completed { "completed" } else { "open" }
+        ),
+        format!(
+            r#"    set tag names of {} to "{}""#,
+            todo_var,
+            escape(&before.tags.join(", "))
+        ),
+    ];
+    if let Some(when) = before.when {
+        steps.push(format!("    schedule {} for {}", todo_var, date_expr(when)));
+    }
+    if let Some(deadline) = before.deadline {
+        steps.push(format!("    set due date of {} to {}", todo_var, date_expr(deadline)));
+    }
+    steps.join("\n")
+}
+
+/// Builds one script applying all of `ops`.  Each runs in its own `try`,
+/// so a failure is reported rather than stopping the rest; the output is
+/// an `ok` record with the todo's name or an `error` record (with the
+/// message and AppleScript error number) per op.
+fn batch_script(ops: &[Op]) -> String {
+    let steps: Vec<String> = ops
+        .iter()
+        .map(|op| {
+            format!(
+                r#"    try
+        set theTodo to to do id "{}"
+        set todoName to name of theTodo
+{}
+        set output to output & my encodeRecord({{"ok", todoName}})
+    on error errorMessage number errorNumber
+        set output to output & my encodeRecord({{"error", errorMessage, errorNumber}})
+    end try"#,
+                escape(op.id()),
+                op_statement(op, "theTodo")
+            )
+        })
+        .collect();
+    format!(
+        r#"{}{}{}
+tell application "Things3"
+    set output to ""
+{}
+    return output
+end tell
+"#,
+        APPLESCRIPT_HANDLERS,
+        TAG_HANDLERS,
+        DATE_HANDLER,
+        steps.join("\n")
+    )
+}
+
+fn parse_batch_results(output: &str, count: usize) -> Result<Vec<Result<String>>> {
+    let results: Vec<Result<String>> = parse_records(output)
+        .map_err(ThingyError::Parse)?
+        .into_iter()
+        .map(|fields| match fields.as_slice() {
+            [status, name] if status == "ok" => Ok(Ok(name.clone())),
+            [_, message, number] => {
+                Ok(Err(ThingyError::from_script(number.parse().ok(), message)))
+            }
+            _ => Err(ThingyError::Parse(format!(
+                "Expected 2 or 3 fields per result, got {}",
+                fields.len()
+            ))),
+        })
+        .collect::<Result<_>>()?;
+    if results.len() != count {
+        return Err(ThingyError::Parse(format!(
+            "Expected {} results from Things, got {}",
+            count,
+            results.len()
+        )));
+    }
+    Ok(results)
+}
+
+impl ThingsBackend {
+    fn apply_single(&self, op: Op) -> Result<String> {
+        self.apply(&[op]).remove(0)
+    }
+}
+
+impl Backend for ThingsBackend {
+    fn fetch_todos(&self, list_name: &str) -> Result<Vec<Todo>> {
+        let selection = format!(
+            "{}\n    {}\n    set todoList to listTodos",
+            select_list(list_name), FILTER_COMPLETED
+        );
+        run_applescript(&fetch_script(&selection))
+            .and_then(|output| parse_todos(&output, list_name, &self.registry()).map_err(ThingyError::Parse))
+    }
+
+    fn fetch_completed(&self, list_name: &str) -> Result<Vec<Todo>> {
+        let selection = format!(
+            r#"{}
+    set todoList to {{}}
+    repeat with todo in to dos of listToQuery
+        if status of todo is completed then
+            set end of todoList to todo
+        end if
+    end repeat"#,
+            select_list(list_name)
+        );
+        run_applescript(&fetch_script(&selection))
+            .and_then(|output| parse_todos(&output, list_name, &self.registry()).map_err(ThingyError::Parse))
+    }
+
+    fn fetch_all(&self, list_name: &str) -> Result<Vec<Todo>> {
+        let selection = format!(
+            "{}\n    set todoList to to dos of listToQuery",
+            select_list(list_name)
+        );
+        run_applescript(&fetch_script(&selection))
+            .and_then(|output| parse_todos(&output, list_name, &self.registry()).map_err(ThingyError::Parse))
+    }
+
+    fn fetch_logbook(&self, days: u32) -> Result<Vec<Todo>> {
+        // The Logbook is ordered most recent first, so stop at the first
+        // todo completed before the cutoff:
+        let selection = format!(
+            r#"    set targetDate to (current date) - ({} * days)
+    set todoList to {{}}
+    repeat with todo in to dos of list "Logbook"
+        set completionDate to completion date of todo
+        if completionDate is not missing value then
+            if completionDate < targetDate then exit repeat
+            set end of todoList to todo
+        end if
+    end repeat"#,
+            days
+        );
+        run_applescript(&fetch_script(&selection))
+            .and_then(|output| parse_todos(&output, "Logbook", &self.registry()).map_err(ThingyError::Parse))
+    }
+
+    fn fetch_notes(&self, id: &str) -> Result<String> {
+        run_applescript(&todo_script(id, "todoItem", "    return notes of todoItem"))
+    }
+
+    fn fetch_lists(&self) -> Result<Vec<ListInfo>> {
+        let script = format!(
+            r#"{}
+tell application "Things3"
+    set output to ""
+    repeat with anArea in areas
+        set output to output & my encodeRecord({{"area", id of anArea, name of anArea, ""}})
+    end repeat
+    repeat with aProject in projects
+        if status of aProject is open then
+            set areaName to ""
+            if area of aProject is not missing value then
+                set areaName to name of area of aProject
+            end if
+            set output to output & my encodeRecord({{"project", id of aProject, name of aProject, areaName}})
+        end if
+    end repeat
+    return output
+end tell
+"#,
+            APPLESCRIPT_HANDLERS
+        );
+
+        let output = run_applescript(&script)?;
+        parse_records(&output)
+            .map_err(ThingyError::Parse)?
+            .into_iter()
+            .map(|fields| match fields.as_slice() {
+                [kind, id, name, area] => Ok(ListInfo {
+                    id: id.clone(),
+                    name: name.clone(),
+                    kind: if kind == "area" { ListKind::Area } else { ListKind::Project },
+                    area: if area.is_empty() { None } else { Some(area.clone()) },
+                    ..Default::default()
+                }),
+                _ => Err(ThingyError::Parse(format!(
+                    "Expected 4 fields per list, got {}",
+                    fields.len()
+                ))),
+            })
+            .collect()
+    }
+
+    fn count_todos(&self, list_name: &str) -> Result<usize> {
+        let script = format!(
+            r#"
+tell application "Things3"
+{}
+    {}
+    return count of listTodos
+end tell
+"#,
+            select_list(list_name), FILTER_COMPLETED
+        );
+
+        let count_str = run_applescript(&script)?;
+        count_str
+            .trim()
+            .parse()
+            .map_err(|_| {
+                ThingyError::Parse(format!("Unexpected count from Things: {}", count_str.trim()))
+            })
+    }
+
+    fn add_todo(&self, list_name: &str, todo: &NewTodo) -> Result<String> {
+        // Checklists and headings aren't scriptable, but the URL scheme
+        // handles them:
+        if !todo.checklist.is_empty() || todo.heading.is_some() {
+            return things_url::add_todo(list_name, todo);
+        }
+
+        let mut steps = vec![move_to_list("newTodo", list_name)];
+        if let Some(deadline) = todo.deadline {
+            steps.push(format!("    set due date of newTodo to {}", date_expr(deadline)));
+        }
+        match todo.when {
+            Some(When::Date(date)) => {
+                steps.push(format!("    schedule newTodo for {}", date_expr(date)));
+            }
+            Some(When::Anytime) if lists::is_built_in(list_name) => {
+                steps.push(r#"    move newTodo to list "Anytime""#.to_string());
+            }
+            Some(When::Someday) if lists::is_built_in(list_name) => {
+                steps.push(r#"    move newTodo to list "Someday""#.to_string());
+            }
+            _ => {}
+        }
+
+        let script = format!(
+            r#"{}
+tell application "Things3"
+    set newTodo to make new to do with properties {{name:"{}", notes:"{}", tag names:"{}"}}
+{}
+    return name of newTodo
+end tell
+"#,
+            DATE_HANDLER,
+            escape(&todo.name),
+            escape(&todo.notes),
+            escape(&todo.tags.join(", ")),
+            steps.join("\n")
+        );
+
+        run_applescript(&script).map(|result| result.trim().to_string())
+    }
+
+    fn create_project(&self, name: &str, area: Option<&str>) -> Result<String> {
+        let set_area = match area {
+            Some(area) => format!(r#"    set area of newProject to area "{}""#, escape(area)),
+            None => String::new(),
+        };
+        let script = format!(
+            r#"
+tell application "Things3"
+    set newProject to make new project with properties {{name:"{}"}}
+{}
+    return name of newProject
+end tell
+"#,
+            escape(name),
+            set_area
+        );
+
+        run_applescript(&script).map(|result| result.trim().to_string())
+    }
+
+    fn complete_project(&self, id: &str) -> Result<String> {
+        let script = format!(
+            r#"
+tell application "Things3"
+    set projectToComplete to project id "{}"
+    repeat with todo in to dos of projectToComplete
+        if status of todo is open then set status of todo to completed
+    end repeat
+    set status of projectToComplete to completed
+    return name of projectToComplete
+end tell
+"#,
+            escape(id)
+        );
+
+        run_applescript(&script).map(|result| result.trim().to_string())
+    }
+
+    fn delete_project(&self, id: &str) -> Result<String> {
+        let script = format!(
+            r#"
+tell application "Things3"
+    set projectToDelete to project id "{}"
+    set projectName to name of projectToDelete
+    delete projectToDelete
+    return projectName
+end tell
+"#,
+            escape(id)
+        );
+
+        run_applescript(&script).map(|result| result.trim().to_string())
+    }
+
+    fn complete_todo(&self, id: &str) -> Result<String> {
+        self.apply_single(Op::Complete { id: id.to_string(), tags: Vec::new() })
+    }
+
+    fn delete_todo(&self, id: &str) -> Result<String> {
+        self.apply_single(Op::Delete { id: id.to_string() })
+    }
+
+    fn move_todo(&self, id: &str, to_list: &str) -> Result<String> {
+        self.apply_single(Op::Move {
+            id: id.to_string(),
+            to_list: to_list.to_string(),
+        })
+    }
+
+    fn edit_tags(&self, id: &str, add: &[&str], remove: &[&str]) -> Result<String> {
+        self.apply_single(Op::EditTags {
+            id: id.to_string(),
+            add: add.iter().map(|t| t.to_string()).collect(),
+            remove: remove.iter().map(|t| t.to_string()).collect(),
+        })
+    }
+
+    fn fetch_tags(&self) -> Result<Vec<TagInfo>> {
+        let script = format!(
+            r#"{}
+tell application "Things3"
+    set output to ""
+    repeat with aTag in tags
+        set parentName to ""
+        if parent tag of aTag is not missing value then
+            set parentName to name of parent tag of aTag
+        end if
+        set openCount to count of (to dos of aTag whose status is open)
+        set output to output & my encodeRecord({{name of aTag, parentName, openCount}})
+    end repeat
+    return output
+end tell
+"#,
+            APPLESCRIPT_HANDLERS
+        );
+
+        let output = run_applescript(&script)?;
+        parse_records(&output)
+            .map_err(ThingyError::Parse)?
+            .into_iter()
+            .map(|fields| match fields.as_slice() {
+                [name, parent, count] => Ok(TagInfo {
+                    name: name.clone(),
+                    parent: if parent.is_empty() { None } else { Some(parent.clone()) },
+                    count: count
+                        .parse()
+                        .map_err(|_| ThingyError::Parse(format!("Invalid tag count '{}'", count)))?,
+                }),
+                _ => Err(ThingyError::Parse(format!(
+                    "Expected 3 fields per tag, got {}",
+                    fields.len()
+                ))),
+            })
+            .collect()
+    }
+
+    fn defer_todo(&self, id: &str) -> Result<String> {
+        self.apply_single(Op::Defer { id: id.to_string() })
+    }
+
+    fn set_completed(&self, id: &str, completed: bool) -> Result<()> {
+        if completed {
+            return self.complete_todo(id).map(|_| ());
+        }
+        let body = "    set status of todoToUpdate to open";
+        run_applescript(&todo_script(id, "todoToUpdate", body)).map(|_| ())
+    }
+
+    fn toggle_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        let body = format!(
+            r#"    if my tagList(todoToUpdate) contains "{tag}" then
+        return my editTags(todoToUpdate, {{}}, {list})
+    else
+        return my editTags(todoToUpdate, {list}, {{}})
+    end if"#,
+            tag = escape(tag),
+            list = applescript_list(&[tag])
+        );
+        run_applescript(&todo_script(id, "todoToUpdate", &body))
+            .map(|result| parse_tag_names(&result))
+    }
+
+    fn log_completed(&self) -> Result<()> {
+        let script = r#"
+tell application "Things3"
+    log completed now
+end tell
+"#;
+        run_applescript(script).map(|_| ())
+    }
+
+    fn restore_todo(&self, id: &str, before: &Snapshot) -> Result<String> {
+        self.apply_single(Op::Restore {
+            id: id.to_string(),
+            before: before.clone(),
+        })
+    }
+
+    fn apply(&self, ops: &[Op]) -> Vec<Result<String>> {
+        if ops.is_empty() {
+            return Vec::new();
+        }
+        let results = run_applescript(&batch_script(ops))
+            .and_then(|output| parse_batch_results(&output, ops.len()));
+        match results {
+            Ok(results) => results,
+            Err(error) => ops.iter().map(|_| Err(error.clone())).collect(),
+        }
+    }
+
+    fn script(&self, ops: &[Op]) -> Option<String> {
+        Some(batch_script(ops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::{RECORD_SEP, UNIT_SEP};
+
+    #[test]
+    fn test_date_expr() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(date_expr(date), "(my makeDate(2026, 3, 9))");
+    }
+
+    #[test]
+    fn test_edit_tags_statement() {
+        assert_eq!(
+            edit_tags_statement("aTodo", &["a \"b\""], &[]),
+            r#"    my editTags(aTodo, {"a \"b\""}, {})"#
+        );
+    }
+
+    #[test]
+    fn test_batch_script() {
+        let script = batch_script(&[
+            Op::Complete { id: "abc".to_string(), tags: Vec::new() },
+            Op::Move { id: "d\"ef".to_string(), to_list: "Someday".to_string() },
+        ]);
+        assert_eq!(script.matches("    try\n").count(), 2);
+        assert!(script.contains(r#"set theTodo to to do id "abc""#));
+        assert!(script.contains(r#"set theTodo to to do id "d\"ef""#));
+        assert!(script.contains("set status of theTodo to completed"));
+        assert!(script.contains(r#"move theTodo to list "Someday""#));
+        assert!(script.contains("on encodeRecord") && script.contains("on editTags"));
+    }
+
+    #[test]
+    fn test_restore_statements() {
+        let before = Snapshot {
+            list: "Inbox".to_string(),
+            completed: false,
+            tags: vec!["errand".to_string(), "in-progress".to_string()],
+            when: None,
+            deadline: NaiveDate::from_ymd_opt(2026, 3, 9),
+        };
+        let script = restore_statements("theTodo", &before);
+        assert!(script.contains(r#"move theTodo to list "Inbox""#));
+        assert!(script.contains("set status of theTodo to open"));
+        assert!(script.contains(r#"set tag names of theTodo to "errand, in-progress""#));
+        assert!(!script.contains("schedule"));
+        assert!(script.contains("set due date of theTodo to (my makeDate(2026, 3, 9))"));
+    }
+
+    #[test]
+    fn test_parse_batch_results() {
+        let record = |status: &str, text: &str| format!("{}{}{}{}", status, UNIT_SEP, text, RECORD_SEP);
+        let output = record("ok", "Buy milk")
+            + &record("error", &format!("Can't get to do id \"x\".{}-1728", UNIT_SEP));
+
+        let results = parse_batch_results(&output, 2).unwrap();
+        assert_eq!(results[0], Ok("Buy milk".to_string()));
+        assert!(matches!(results[1], Err(ThingyError::NotFound { .. })));
+        assert!(parse_batch_results(&output, 3).is_err());
+    }
+}
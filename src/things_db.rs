@@ -0,0 +1,608 @@
+// Reading Things3's own SQLite database.  Things3 keeps todos, projects
+// and headings in `TMTask` (told apart by `type`), areas in `TMArea` and
+// tags in `TMTag`, linked to todos through `TMTaskTag`.  The database is
+// opened read-only: Things3 owns it, so changes go through AppleScript or
+// the URL scheme, whichever `writes` picks.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+
+use crate::backend::{Backend, Op};
+use crate::error::{Result, ThingyError};
+use crate::identifiers::assign_identifiers;
+use crate::journal::{Journal, Snapshot};
+use crate::lists::{ListInfo, ListKind};
+use crate::registry::RegistryStore;
+use crate::tags::TagInfo;
+use crate::todo::{parse_tag_names, NewTodo, Todo};
+
+/// `TMTask.type` of a todo and of a project.
+const TODO: i64 = 0;
+const PROJECT: i64 = 1;
+
+/// `TMTask.status` of an open and of a completed task.  Canceled tasks
+/// (2) only show up in the Logbook.
+const OPEN: i64 = 0;
+const COMPLETED: i64 = 3;
+
+/// A todo's fields, in the order `todo_from_row` reads them.  Its project
+/// is either its own or its heading's.
+const TODO_QUERY: &str = r#"
+SELECT t.uuid, t.status, t.title, t.startDate, t.deadline, t.creationDate, t.stopDate,
+       COALESCE(p.title, hp.title), t.notes,
+       (SELECT group_concat(title, ', ') FROM
+           (SELECT tag.title FROM TMTaskTag tt JOIN TMTag tag ON tag.uuid = tt.tags
+            WHERE tt.tasks = t.uuid ORDER BY tag."index"))
+FROM TMTask t
+LEFT JOIN TMTask p ON p.uuid = t.project
+LEFT JOIN TMTask h ON h.uuid = t.heading
+LEFT JOIN TMTask hp ON hp.uuid = h.project
+WHERE t.type = 0 AND t.rt1_recurrenceRule IS NULL"#;
+
+/// A Things3 backend that reads from Things3's database and passes
+/// changes on to `writes`.
+pub struct ThingsDbBackend {
+    db: Connection,
+    writes: Box<dyn Backend>,
+}
+
+impl ThingsDbBackend {
+    /// Opens the database at `path` read-only.
+    pub fn open(path: &Path, writes: Box<dyn Backend>) -> Result<Self> {
+        if !path.exists() {
+            return Err(ThingyError::not_found(
+                format!("No Things3 database at {}", path.display()),
+                database_hint(),
+            ));
+        }
+        let db = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(|e| ThingyError::Other(format!("Can't open {}: {}", path.display(), e)))?;
+        Ok(ThingsDbBackend { db, writes })
+    }
+
+    /// Todos in `list_name` whose status is `status` (any if None).
+    fn query_todos(&self, list_name: &str, status: Option<i64>) -> Result<Vec<Todo>> {
+        let today = pack_date(Local::now().date_naive());
+        let (condition, order) = match list_name {
+            "Inbox" => ("t.trashed = 0 AND t.start = 0".to_string(), r#"t."index""#),
+            "Today" => (
+                format!("t.trashed = 0 AND t.start IN (1, 2) AND t.startDate <= {}", today),
+                r#"t.todayIndex, t."index""#,
+            ),
+            "Anytime" => (
+                format!(
+                    "t.trashed = 0 AND t.start = 1 AND (t.startDate IS NULL OR t.startDate <= {})",
+                    today
+                ),
+                r#"t."index""#,
+            ),
+            "Upcoming" => (format!("t.trashed = 0 AND t.startDate > {}", today), "t.startDate"),
+            "Someday" => (
+                "t.trashed = 0 AND t.start = 2 AND t.startDate IS NULL".to_string(),
+                r#"t."index""#,
+            ),
+            "Logbook" => ("t.trashed = 0 AND t.status != 0".to_string(), "t.stopDate DESC"),
+            "Trash" => ("t.trashed = 1".to_string(), r#"t."index""#),
+            _ => {
+                self.check_list_exists(list_name)?;
+                let condition = "t.trashed = 0 AND (p.title = ?1 OR hp.title = ?1 \
+                                 OR t.area IN (SELECT uuid FROM TMArea WHERE title = ?1))";
+                let sql = todo_sql(condition, status, r#"t."index""#);
+                return self.todos(&sql, params![list_name], list_name);
+            }
+        };
+        self.todos(&todo_sql(&condition, status, order), [], list_name)
+    }
+
+    fn check_list_exists(&self, list_name: &str) -> Result<()> {
+        let exists: bool = self
+            .db
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM TMTask WHERE type = ?1 AND trashed = 0 AND title = ?2)
+                     OR EXISTS (SELECT 1 FROM TMArea WHERE title = ?2)",
+                params![PROJECT, list_name],
+                |row| row.get(0),
+            )
+            .map_err(db_error)?;
+        if exists {
+            Ok(())
+        } else {
+            Err(ThingyError::not_found(format!("No list named '{}'", list_name), None))
+        }
+    }
+
+    /// Runs `sql`, a `TODO_QUERY`, for the todos in `list_name`.
+    fn todos(&self, sql: &str, params: impl rusqlite::Params, list_name: &str) -> Result<Vec<Todo>> {
+        let mut statement = self.db.prepare(sql).map_err(db_error)?;
+        let mut todos = statement
+            .query_map(params, |row| todo_from_row(row, list_name))
+            .map_err(db_error)?
+            .collect::<rusqlite::Result<Vec<Todo>>>()
+            .map_err(db_error)?;
+        for (idx, todo) in todos.iter_mut().enumerate() {
+            todo.index = idx + 1;
+        }
+        assign_identifiers(&mut todos, &self.writes.registry());
+        Ok(todos)
+    }
+}
+
+/// A `TODO_QUERY` for todos meeting `condition` whose status is
+/// `status` (any if None).
+fn todo_sql(condition: &str, status: Option<i64>, order: &str) -> String {
+    let status_condition = match status {
+        Some(status) => format!(" AND t.status = {}", status),
+        None => String::new(),
+    };
+    format!("{} AND {}{} ORDER BY {}", TODO_QUERY, condition, status_condition, order)
+}
+
+fn todo_from_row(row: &Row, list_name: &str) -> rusqlite::Result<Todo> {
+    Ok(Todo {
+        id: row.get(0)?,
+        is_completed: row.get::<_, i64>(1)? == COMPLETED,
+        name: row.get(2)?,
+        when: row.get::<_, Option<i64>>(3)?.and_then(unpack_date),
+        deadline: row.get::<_, Option<i64>>(4)?.and_then(unpack_date),
+        created: row.get::<_, Option<f64>>(5)?.and_then(timestamp_date),
+        completed_on: row.get::<_, Option<f64>>(6)?.and_then(timestamp_date),
+        project: row.get(7)?,
+        notes: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+        tags: parse_tag_names(&row.get::<_, Option<String>>(9)?.unwrap_or_default()),
+        list: list_name.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Things3 stores the dates a todo is scheduled for and due on as
+/// `year << 16 | month << 12 | day << 7`.
+fn pack_date(date: NaiveDate) -> i64 {
+    (date.year() as i64) << 16 | (date.month() as i64) << 12 | (date.day() as i64) << 7
+}
+
+fn unpack_date(packed: i64) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(
+        (packed >> 16) as i32,
+        ((packed >> 12) & 0xF) as u32,
+        ((packed >> 7) & 0x1F) as u32,
+    )
+}
+
+/// The local date of a Unix timestamp, as Things3 stores when a task
+/// was created and completed.
+fn timestamp_date(timestamp: f64) -> Option<NaiveDate> {
+    DateTime::from_timestamp(timestamp as i64, 0).map(|time| time.with_timezone(&Local).date_naive())
+}
+
+fn db_error(error: rusqlite::Error) -> ThingyError {
+    ThingyError::Other(format!("Error reading the Things3 database: {}", error))
+}
+
+fn database_hint() -> Option<String> {
+    Some(
+        "Set database under [things] in the config file, or reads = \"applescript\" \
+         to read through AppleScript"
+            .to_string(),
+    )
+}
+
+/// Where Things3 keeps its database, in a `ThingsData-*` folder of its
+/// group container (directly in the container before Things 3.15).
+pub fn default_path() -> Result<PathBuf> {
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    let container = PathBuf::from(home)
+        .join("Library/Group Containers/JLMPQHK86H.com.culturedcode.ThingsMac");
+    let database = Path::new("Things Database.thingsdatabase").join("main.sqlite");
+
+    let mut data_dirs: Vec<PathBuf> = fs::read_dir(&container)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("ThingsData-"))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    data_dirs.sort();
+    data_dirs.push(container);
+    data_dirs
+        .into_iter()
+        .map(|dir| dir.join(&database))
+        .find(|path| path.exists())
+        .ok_or_else(|| ThingyError::not_found("Couldn't find the Things3 database", database_hint()))
+}
+
+impl Backend for ThingsDbBackend {
+    /// Nothing in the Logbook is open, so its todos are all it lists.
+    fn fetch_todos(&self, list_name: &str) -> Result<Vec<Todo>> {
+        match list_name {
+            "Logbook" => self.query_todos(list_name, None),
+            _ => self.query_todos(list_name, Some(OPEN)),
+        }
+    }
+
+    fn fetch_completed(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.query_todos(list_name, Some(COMPLETED))
+    }
+
+    fn fetch_all(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.query_todos(list_name, None)
+    }
+
+    fn fetch_logbook(&self, days: u32) -> Result<Vec<Todo>> {
+        let cutoff = Local::now().timestamp() - days as i64 * 86400;
+        let sql = format!(
+            "{} AND t.trashed = 0 AND t.status != 0 AND t.stopDate >= ?1 ORDER BY t.stopDate DESC",
+            TODO_QUERY
+        );
+        self.todos(&sql, params![cutoff as f64], "Logbook")
+    }
+
+    fn fetch_notes(&self, id: &str) -> Result<String> {
+        self.db
+            .query_row("SELECT notes FROM TMTask WHERE uuid = ?1", params![id], |row| {
+                row.get::<_, Option<String>>(0)
+            })
+            .optional()
+            .map_err(db_error)?
+            .map(|notes| notes.unwrap_or_default())
+            .ok_or_else(|| ThingyError::not_found(format!("No todo with id {}", id), None))
+    }
+
+    fn fetch_lists(&self) -> Result<Vec<ListInfo>> {
+        let mut lists = Vec::new();
+        let mut areas = self
+            .db
+            .prepare(r#"SELECT uuid, title FROM TMArea ORDER BY "index""#)
+            .map_err(db_error)?;
+        for area in areas
+            .query_map([], |row| {
+                Ok(ListInfo {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    kind: ListKind::Area,
+                    ..Default::default()
+                })
+            })
+            .map_err(db_error)?
+        {
+            lists.push(area.map_err(db_error)?);
+        }
+
+        let mut projects = self
+            .db
+            .prepare(
+                r#"SELECT p.uuid, p.title, a.title FROM TMTask p
+                   LEFT JOIN TMArea a ON a.uuid = p.area
+                   WHERE p.type = ?1 AND p.status = ?2 AND p.trashed = 0
+                   ORDER BY p."index""#,
+            )
+            .map_err(db_error)?;
+        for project in projects
+            .query_map(params![PROJECT, OPEN], |row| {
+                Ok(ListInfo {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    kind: ListKind::Project,
+                    area: row.get(2)?,
+                    ..Default::default()
+                })
+            })
+            .map_err(db_error)?
+        {
+            lists.push(project.map_err(db_error)?);
+        }
+        Ok(lists)
+    }
+
+    fn fetch_tags(&self) -> Result<Vec<TagInfo>> {
+        let mut statement = self
+            .db
+            .prepare(
+                r#"SELECT tag.title, parent.title,
+                       (SELECT COUNT(*) FROM TMTaskTag tt JOIN TMTask t ON t.uuid = tt.tasks
+                        WHERE tt.tags = tag.uuid AND t.type = ?1 AND t.status = ?2 AND t.trashed = 0)
+                   FROM TMTag tag LEFT JOIN TMTag parent ON parent.uuid = tag.parent
+                   ORDER BY tag."index""#,
+            )
+            .map_err(db_error)?;
+        let tags = statement
+            .query_map(params![TODO, OPEN], |row| {
+                Ok(TagInfo {
+                    name: row.get(0)?,
+                    parent: row.get(1)?,
+                    count: row.get::<_, i64>(2)? as usize,
+                })
+            })
+            .map_err(db_error)?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(db_error)?;
+        Ok(tags)
+    }
+
+    fn add_todo(&self, list_name: &str, todo: &NewTodo) -> Result<String> {
+        self.writes.add_todo(list_name, todo)
+    }
+
+    fn create_project(&self, name: &str, area: Option<&str>) -> Result<String> {
+        self.writes.create_project(name, area)
+    }
+
+    fn complete_project(&self, id: &str) -> Result<String> {
+        self.writes.complete_project(id)
+    }
+
+    fn delete_project(&self, id: &str) -> Result<String> {
+        self.writes.delete_project(id)
+    }
+
+    fn complete_todo(&self, id: &str) -> Result<String> {
+        self.writes.complete_todo(id)
+    }
+
+    fn delete_todo(&self, id: &str) -> Result<String> {
+        self.writes.delete_todo(id)
+    }
+
+    fn move_todo(&self, id: &str, to_list: &str) -> Result<String> {
+        self.writes.move_todo(id, to_list)
+    }
+
+    fn edit_tags(&self, id: &str, add: &[&str], remove: &[&str]) -> Result<String> {
+        self.writes.edit_tags(id, add, remove)
+    }
+
+    fn defer_todo(&self, id: &str) -> Result<String> {
+        self.writes.defer_todo(id)
+    }
+
+    fn set_completed(&self, id: &str, completed: bool) -> Result<()> {
+        self.writes.set_completed(id, completed)
+    }
+
+    fn toggle_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        self.writes.toggle_tag(id, tag)
+    }
+
+    fn log_completed(&self) -> Result<()> {
+        self.writes.log_completed()
+    }
+
+    fn restore_todo(&self, id: &str, before: &Snapshot) -> Result<String> {
+        self.writes.restore_todo(id, before)
+    }
+
+    fn apply(&self, ops: &[Op]) -> Vec<Result<String>> {
+        self.writes.apply(ops)
+    }
+
+    fn script(&self, ops: &[Op]) -> Option<String> {
+        self.writes.script(ops)
+    }
+
+    fn journal(&self) -> Journal {
+        self.writes.journal()
+    }
+
+    fn registry(&self) -> RegistryStore {
+        self.writes.registry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_backend::FileBackend;
+    use chrono::{Duration, TimeZone};
+
+    /// The parts of Things3's schema we read.
+    const SCHEMA: &str = r#"
+CREATE TABLE TMArea (uuid TEXT PRIMARY KEY, title TEXT, "index" INTEGER);
+CREATE TABLE TMTask (
+    uuid TEXT PRIMARY KEY, type INTEGER, status INTEGER, trashed INTEGER,
+    title TEXT, notes TEXT, start INTEGER, startDate INTEGER, deadline INTEGER,
+    creationDate REAL, stopDate REAL, "index" INTEGER, todayIndex INTEGER,
+    area TEXT, project TEXT, heading TEXT, rt1_recurrenceRule BLOB
+);
+CREATE TABLE TMTag (uuid TEXT PRIMARY KEY, title TEXT, parent TEXT, "index" INTEGER);
+CREATE TABLE TMTaskTag (tasks TEXT, tags TEXT);
+"#;
+
+    fn today() -> NaiveDate {
+        Local::now().date_naive()
+    }
+
+    /// Noon on `date`, as a Things3 timestamp.
+    fn timestamp(date: NaiveDate) -> f64 {
+        Local
+            .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+            .unwrap()
+            .timestamp() as f64
+    }
+
+    struct Task<'a> {
+        uuid: &'a str,
+        kind: i64,
+        status: i64,
+        title: &'a str,
+        start: i64,
+        start_date: Option<NaiveDate>,
+        project: Option<&'a str>,
+        heading: Option<&'a str>,
+        area: Option<&'a str>,
+        stopped: Option<NaiveDate>,
+    }
+
+    impl Default for Task<'_> {
+        fn default() -> Self {
+            Task {
+                uuid: "",
+                kind: TODO,
+                status: OPEN,
+                title: "",
+                start: 1,
+                start_date: None,
+                project: None,
+                heading: None,
+                area: None,
+                stopped: None,
+            }
+        }
+    }
+
+    fn insert(db: &Connection, index: i64, task: Task) {
+        db.execute(
+            r#"INSERT INTO TMTask (uuid, type, status, trashed, title, notes, start, startDate,
+                   deadline, creationDate, stopDate, "index", todayIndex, area, project, heading)
+               VALUES (?1, ?2, ?3, 0, ?4, '', ?5, ?6, NULL, ?7, ?8, ?9, ?9, ?10, ?11, ?12)"#,
+            params![
+                task.uuid,
+                task.kind,
+                task.status,
+                task.title,
+                task.start,
+                task.start_date.map(pack_date),
+                timestamp(NaiveDate::from_ymd_opt(2026, 10, 1).unwrap()),
+                task.stopped.map(timestamp),
+                index,
+                task.area,
+                task.project,
+                task.heading,
+            ],
+        )
+        .unwrap();
+    }
+
+    /// A database with an area holding a project with a heading, and
+    /// todos in the Inbox, Today, Someday, the project and the Logbook.
+    fn fixture() -> ThingsDbBackend {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(SCHEMA).unwrap();
+        db.execute_batch(
+            r#"INSERT INTO TMArea VALUES ('a1', 'Home', 1);
+               INSERT INTO TMTag VALUES ('g1', 'errand', NULL, 1), ('g2', 'shop', 'g1', 2);
+               INSERT INTO TMTaskTag VALUES ('t2', 'g2'), ('t2', 'g1'), ('t5', 'g1');"#,
+        )
+        .unwrap();
+        let yesterday = today() - Duration::days(1);
+        let tasks = [
+            Task { uuid: "p1", kind: PROJECT, title: "Garden", area: Some("a1"), ..Default::default() },
+            Task { uuid: "h1", kind: 2, title: "Spring", project: Some("p1"), ..Default::default() },
+            Task { uuid: "t1", title: "Call mom", start: 0, ..Default::default() },
+            Task { uuid: "t2", title: "Buy milk", start_date: Some(today()), ..Default::default() },
+            Task { uuid: "t3", title: "Learn Go", start: 2, ..Default::default() },
+            Task { uuid: "t4", title: "Plant bulbs", heading: Some("h1"), ..Default::default() },
+            Task {
+                uuid: "t5",
+                status: COMPLETED,
+                title: "Pay rent",
+                start_date: Some(yesterday),
+                stopped: Some(yesterday),
+                ..Default::default()
+            },
+            Task {
+                uuid: "t6",
+                status: COMPLETED,
+                title: "File taxes",
+                stopped: Some(today() - Duration::days(30)),
+                ..Default::default()
+            },
+        ];
+        for (index, task) in tasks.into_iter().enumerate() {
+            insert(&db, index as i64, task);
+        }
+        db.execute("UPDATE TMTask SET notes = '2%' WHERE uuid = 't2'", []).unwrap();
+        ThingsDbBackend {
+            db,
+            writes: Box::new(FileBackend::in_memory()),
+        }
+    }
+
+    fn names(todos: &[Todo]) -> Vec<&str> {
+        todos.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_packed_dates() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        assert_eq!(pack_date(date), 132_819_072);
+        assert_eq!(unpack_date(pack_date(date)), Some(date));
+    }
+
+    #[test]
+    fn test_fetch_built_in_lists() {
+        let backend = fixture();
+        assert_eq!(names(&backend.fetch_todos("Inbox").unwrap()), ["Call mom"]);
+        assert_eq!(names(&backend.fetch_todos("Someday").unwrap()), ["Learn Go"]);
+        assert_eq!(names(&backend.fetch_all("Today").unwrap()), ["Buy milk", "Pay rent"]);
+        assert_eq!(names(&backend.fetch_completed("Today").unwrap()), ["Pay rent"]);
+
+        let todos = backend.fetch_todos("Today").unwrap();
+        let milk = &todos[0];
+        assert_eq!((milk.index, milk.list.as_str()), (1, "Today"));
+        assert!(!milk.identifier.is_empty());
+        assert_eq!(milk.tags, ["errand", "shop"]);
+        assert_eq!(milk.when, Some(today()));
+        assert_eq!(milk.created, NaiveDate::from_ymd_opt(2026, 10, 1));
+        assert_eq!(milk.notes, "2%");
+    }
+
+    #[test]
+    fn test_fetch_projects_and_areas() {
+        let backend = fixture();
+        let garden = backend.fetch_todos("Garden").unwrap();
+        assert_eq!(names(&garden), ["Plant bulbs"]);
+        assert_eq!(garden[0].project.as_deref(), Some("Garden"));
+        assert!(matches!(
+            backend.fetch_todos("Nowhere"),
+            Err(ThingyError::NotFound { .. })
+        ));
+
+        let lists = backend.fetch_lists().unwrap();
+        assert_eq!(lists.len(), 2);
+        assert_eq!((lists[0].name.as_str(), lists[0].kind), ("Home", ListKind::Area));
+        assert_eq!((lists[1].name.as_str(), lists[1].kind), ("Garden", ListKind::Project));
+        assert_eq!(lists[1].area.as_deref(), Some("Home"));
+    }
+
+    #[test]
+    fn test_fetch_logbook() {
+        let backend = fixture();
+        let logbook = backend.fetch_logbook(7).unwrap();
+        assert_eq!(names(&logbook), ["Pay rent"]);
+        assert!(logbook[0].is_completed);
+        assert_eq!(logbook[0].completed_on, Some(today() - Duration::days(1)));
+        assert_eq!(names(&backend.fetch_logbook(60).unwrap()), ["Pay rent", "File taxes"]);
+
+        let listed = backend.fetch_todos("Logbook").unwrap();
+        assert_eq!(names(&listed), ["Pay rent", "File taxes"]);
+        assert_eq!((listed[0].index, listed[0].list.as_str()), (1, "Logbook"));
+        assert_eq!(backend.count_todos("Logbook").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_fetch_tags_and_notes() {
+        let backend = fixture();
+        let tags = backend.fetch_tags().unwrap();
+        assert_eq!(tags[0], TagInfo { name: "errand".to_string(), parent: None, count: 1 });
+        assert_eq!(tags[1].parent.as_deref(), Some("errand"));
+
+        assert_eq!(backend.fetch_notes("t2").unwrap(), "2%");
+        assert!(matches!(backend.fetch_notes("zz"), Err(ThingyError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_writes_go_to_the_write_backend() {
+        let backend = fixture();
+        backend.add_todo("Inbox", &NewTodo::new("Water plants")).unwrap();
+        assert_eq!(names(&backend.writes.fetch_todos("Inbox").unwrap()), ["Water plants"]);
+        assert_eq!(names(&backend.fetch_todos("Inbox").unwrap()), ["Call mom"]);
+    }
+}
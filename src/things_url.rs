@@ -0,0 +1,440 @@
+// Changing Things3 through its URL scheme: `things:///add` and
+// `things:///add-project` for new todos and projects, and one
+// `things:///json` URL for a batch of updates, which Things3 only
+// accepts with the auth token from its Settings.  Opening a URL is much
+// faster than running AppleScript, but Things3 reports nothing back, and
+// some changes can't be expressed; those still go through AppleScript.
+
+use serde_json::{json, Value};
+
+use crate::backend::{Backend, Op};
+use crate::config;
+use crate::error::{Result, ThingyError};
+use crate::journal::{Journal, Snapshot};
+use crate::lists::{self, ListInfo};
+use crate::registry::RegistryStore;
+use crate::tags::TagInfo;
+use crate::todo::{NewTodo, Todo, When};
+
+/// A Things3 backend that makes what changes it can through URLs and
+/// passes everything else, reads included, on to `inner`.
+pub struct ThingsUrlBackend {
+    inner: Box<dyn Backend>,
+    auth_token: String,
+}
+
+impl ThingsUrlBackend {
+    pub fn new(inner: Box<dyn Backend>, auth_token: impl Into<String>) -> Self {
+        ThingsUrlBackend {
+            inner,
+            auth_token: auth_token.into(),
+        }
+    }
+
+    fn apply_single(&self, op: Op) -> Result<String> {
+        self.apply(&[op]).remove(0)
+    }
+
+    /// The updates a `things:///json` URL can make for `ops`, and the ops
+    /// it can't.
+    fn split(&self, ops: &[Op]) -> (Vec<Value>, Vec<Op>) {
+        let mut updates = Vec::new();
+        let mut rest = Vec::new();
+        for op in ops {
+            match update_json(op) {
+                Some(update) => updates.push(update),
+                None => rest.push(op.clone()),
+            }
+        }
+        (updates, rest)
+    }
+}
+
+/// Percent-encodes `s` for a URL query value.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn url(command: &str, params: &[(&str, String)]) -> String {
+    let query: Vec<String> = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+        .collect();
+    format!("things:///{}?{}", command, query.join("&"))
+}
+
+/// Whether `things:///add` can put a todo in `list_name`: any project or
+/// area, but of the built-in lists only those a `when` selects.
+fn can_add_to(list_name: &str) -> bool {
+    !lists::is_built_in(list_name) || matches!(list_name, "Inbox" | "Today" | "Anytime" | "Someday")
+}
+
+/// The `things:///add` URL adding `todo` to `list_name`.
+fn add_url(list_name: &str, todo: &NewTodo) -> Result<String> {
+    if !can_add_to(list_name) {
+        return Err(ThingyError::Other(format!(
+            "Todos with a checklist or heading can't be added to {}",
+            list_name
+        )));
+    }
+    let mut params = vec![("title", todo.name.clone())];
+    let when = match todo.when {
+        Some(When::Date(date)) => Some(date.to_string()),
+        Some(When::Anytime) => Some("anytime".to_string()),
+        Some(When::Someday) => Some("someday".to_string()),
+        None => None,
+    };
+    let list_when = match list_name {
+        "Today" | "Anytime" | "Someday" => Some(list_name.to_lowercase()),
+        "Inbox" => None,
+        _ => {
+            params.push(("list", list_name.to_string()));
+            None
+        }
+    };
+    if let Some(when) = when.or(list_when) {
+        params.push(("when", when));
+    }
+    if let Some(heading) = &todo.heading {
+        params.push(("heading", heading.clone()));
+    }
+    if !todo.notes.is_empty() {
+        params.push(("notes", todo.notes.clone()));
+    }
+    if !todo.tags.is_empty() {
+        params.push(("tags", todo.tags.join(",")));
+    }
+    if let Some(deadline) = todo.deadline {
+        params.push(("deadline", deadline.to_string()));
+    }
+    if !todo.checklist.is_empty() {
+        params.push(("checklist-items", todo.checklist.join("\n")));
+    }
+    Ok(url("add", &params))
+}
+
+fn add_project_url(name: &str, area: Option<&str>) -> String {
+    let mut params = vec![("title", name.to_string())];
+    if let Some(area) = area {
+        params.push(("area", area.to_string()));
+    }
+    url("add-project", &params)
+}
+
+/// The `things:///json` update making `op`, if the URL scheme can.  It
+/// can't remove tags, so it can't complete a todo with the in-progress
+/// tag.  Editing tags may create them, which it can't do either; nor can
+/// it delete, restore or move to the Inbox, Upcoming, Logbook or Trash.
+fn update_json(op: &Op) -> Option<Value> {
+    let attributes = match op {
+        Op::Complete { tags, .. } if tags.contains(&config::get().tags.in_progress) => return None,
+        Op::Complete { .. } => json!({ "completed": true }),
+        Op::Defer { .. } => json!({ "when": "tomorrow" }),
+        Op::Move { to_list, .. } => match to_list.as_str() {
+            "Today" | "Anytime" | "Someday" => json!({ "when": to_list.to_lowercase() }),
+            list if !lists::is_built_in(list) => json!({ "list": list }),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(json!({
+        "type": "to-do",
+        "operation": "update",
+        "id": op.id(),
+        "attributes": attributes,
+    }))
+}
+
+fn json_url(auth_token: &str, updates: &[Value]) -> String {
+    url(
+        "json",
+        &[
+            ("auth-token", auth_token.to_string()),
+            ("data", Value::Array(updates.to_vec()).to_string()),
+        ],
+    )
+}
+
+/// `json_url` as shown by `--show-script`, without the auth token.
+fn masked_json_url(updates: &[Value]) -> String {
+    json_url("", updates).replacen("auth-token=", "auth-token=***", 1)
+}
+
+/// Adds `todo` through the `things:///add` URL.  The URL scheme reports
+/// nothing back, so this returns the name as given.
+pub(crate) fn add_todo(list_name: &str, todo: &NewTodo) -> Result<String> {
+    open_url(&add_url(list_name, todo)?)?;
+    Ok(todo.name.clone())
+}
+
+/// Hands a `things:///` URL to Things3 without bringing it to the front.
+fn open_url(url: &str) -> Result<()> {
+    let status = std::process::Command::new("open")
+        .arg("-g")
+        .arg(url)
+        .status()
+        .map_err(|e| ThingyError::Other(format!("Failed to run open: {}", e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ThingyError::Other(format!("open exited with {}", status)))
+    }
+}
+
+impl Backend for ThingsUrlBackend {
+    fn fetch_todos(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.inner.fetch_todos(list_name)
+    }
+
+    fn fetch_completed(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.inner.fetch_completed(list_name)
+    }
+
+    fn fetch_all(&self, list_name: &str) -> Result<Vec<Todo>> {
+        self.inner.fetch_all(list_name)
+    }
+
+    fn fetch_logbook(&self, days: u32) -> Result<Vec<Todo>> {
+        self.inner.fetch_logbook(days)
+    }
+
+    fn fetch_notes(&self, id: &str) -> Result<String> {
+        self.inner.fetch_notes(id)
+    }
+
+    fn fetch_lists(&self) -> Result<Vec<ListInfo>> {
+        self.inner.fetch_lists()
+    }
+
+    fn fetch_tags(&self) -> Result<Vec<TagInfo>> {
+        self.inner.fetch_tags()
+    }
+
+    fn count_todos(&self, list_name: &str) -> Result<usize> {
+        self.inner.count_todos(list_name)
+    }
+
+    fn add_todo(&self, list_name: &str, todo: &NewTodo) -> Result<String> {
+        if !can_add_to(list_name) {
+            return self.inner.add_todo(list_name, todo);
+        }
+        add_todo(list_name, todo)
+    }
+
+    fn create_project(&self, name: &str, area: Option<&str>) -> Result<String> {
+        open_url(&add_project_url(name, area))?;
+        Ok(name.to_string())
+    }
+
+    fn complete_project(&self, id: &str) -> Result<String> {
+        // Things3 ignores completing a project with open todos by URL:
+        self.inner.complete_project(id)
+    }
+
+    fn delete_project(&self, id: &str) -> Result<String> {
+        self.inner.delete_project(id)
+    }
+
+    /// Goes through `inner`, as the todo may have the in-progress tag.
+    fn complete_todo(&self, id: &str) -> Result<String> {
+        self.inner.complete_todo(id)
+    }
+
+    fn delete_todo(&self, id: &str) -> Result<String> {
+        self.apply_single(Op::Delete { id: id.to_string() })
+    }
+
+    fn move_todo(&self, id: &str, to_list: &str) -> Result<String> {
+        self.apply_single(Op::Move {
+            id: id.to_string(),
+            to_list: to_list.to_string(),
+        })
+    }
+
+    fn edit_tags(&self, id: &str, add: &[&str], remove: &[&str]) -> Result<String> {
+        self.inner.edit_tags(id, add, remove)
+    }
+
+    fn defer_todo(&self, id: &str) -> Result<String> {
+        self.apply_single(Op::Defer { id: id.to_string() })
+    }
+
+    fn set_completed(&self, id: &str, completed: bool) -> Result<()> {
+        self.inner.set_completed(id, completed)
+    }
+
+    fn toggle_tag(&self, id: &str, tag: &str) -> Result<Vec<String>> {
+        self.inner.toggle_tag(id, tag)
+    }
+
+    fn log_completed(&self) -> Result<()> {
+        self.inner.log_completed()
+    }
+
+    fn restore_todo(&self, id: &str, before: &Snapshot) -> Result<String> {
+        self.inner.restore_todo(id, before)
+    }
+
+    /// Makes the updates the URL scheme can in one `things:///json` URL
+    /// and the rest through `inner`.  Things3 doesn't confirm URL updates,
+    /// so they come back with empty names.
+    fn apply(&self, ops: &[Op]) -> Vec<Result<String>> {
+        let (updates, rest) = self.split(ops);
+        let opened = match updates.len() {
+            0 => Ok(()),
+            _ => open_url(&json_url(&self.auth_token, &updates)),
+        };
+        let mut rest_results = match rest.len() {
+            0 => Vec::new(),
+            _ => self.inner.apply(&rest),
+        }
+        .into_iter();
+        ops.iter()
+            .map(|op| match update_json(op) {
+                Some(_) => opened.clone().map(|()| String::new()),
+                None => rest_results.next().unwrap_or_else(|| {
+                    Err(ThingyError::Other(format!("No result for {}", op.id())))
+                }),
+            })
+            .collect()
+    }
+
+    fn script(&self, ops: &[Op]) -> Option<String> {
+        let (updates, rest) = self.split(ops);
+        let mut parts = Vec::new();
+        if !updates.is_empty() {
+            parts.push(format!("open -g '{}'", masked_json_url(&updates)));
+        }
+        if !rest.is_empty() {
+            parts.extend(self.inner.script(&rest));
+        }
+        Some(parts.join("\n\n"))
+    }
+
+    fn refresh(&self) {
+        self.inner.refresh();
+    }
+
+    fn journal(&self) -> Journal {
+        self.inner.journal()
+    }
+
+    fn registry(&self) -> RegistryStore {
+        self.inner.registry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("Buy milk"), "Buy%20milk");
+        assert_eq!(percent_encode("a&b=c?d/e"), "a%26b%3Dc%3Fd%2Fe");
+        assert_eq!(percent_encode("café"), "caf%C3%A9");
+        assert_eq!(percent_encode("plain-text_1.0~"), "plain-text_1.0~");
+    }
+
+    #[test]
+    fn test_add_url() {
+        let todo = NewTodo {
+            name: "Buy milk & eggs".to_string(),
+            tags: vec!["errand".to_string(), "15m".to_string()],
+            deadline: NaiveDate::from_ymd_opt(2026, 11, 1),
+            checklist: vec!["milk".to_string(), "eggs".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            add_url("Today", &todo).unwrap(),
+            "things:///add?title=Buy%20milk%20%26%20eggs&when=today&tags=errand%2C15m\
+             &deadline=2026-11-01&checklist-items=milk%0Aeggs"
+        );
+
+        let todo = NewTodo {
+            name: "Plant bulbs".to_string(),
+            when: Some(When::Someday),
+            heading: Some("Spring".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            add_url("Garden", &todo).unwrap(),
+            "things:///add?title=Plant%20bulbs&list=Garden&when=someday&heading=Spring"
+        );
+        assert_eq!(add_url("Inbox", &NewTodo::new("Call mom")).unwrap(), "things:///add?title=Call%20mom");
+        assert!(add_url("Upcoming", &todo).is_err());
+    }
+
+    #[test]
+    fn test_add_project_url() {
+        assert_eq!(add_project_url("Garden", None), "things:///add-project?title=Garden");
+        assert_eq!(
+            add_project_url("Tax return", Some("Home")),
+            "things:///add-project?title=Tax%20return&area=Home"
+        );
+    }
+
+    #[test]
+    fn test_updates() {
+        let defer = Op::Defer { id: "A1".to_string() };
+        let to_project = Op::Move { id: "B2".to_string(), to_list: "Garden".to_string() };
+        let to_someday = Op::Move { id: "C3".to_string(), to_list: "Someday".to_string() };
+        let to_inbox = Op::Move { id: "D4".to_string(), to_list: "Inbox".to_string() };
+        let complete = Op::Complete { id: "E5".to_string(), tags: vec!["errand".to_string()] };
+
+        assert_eq!(update_json(&defer).unwrap()["attributes"], json!({ "when": "tomorrow" }));
+        assert_eq!(update_json(&to_project).unwrap()["attributes"], json!({ "list": "Garden" }));
+        assert_eq!(update_json(&to_someday).unwrap()["attributes"], json!({ "when": "someday" }));
+        assert_eq!(update_json(&to_inbox), None);
+        assert_eq!(update_json(&complete).unwrap()["attributes"], json!({ "completed": true }));
+        assert_eq!(update_json(&Op::Delete { id: "F6".to_string() }), None);
+
+        let url = json_url("s3cr&t", &[update_json(&defer).unwrap()]);
+        let prefix = "things:///json?auth-token=s3cr%26t&data=";
+        assert!(url.starts_with(prefix));
+        let data = url[prefix.len()..].replace("%22", "\"").replace("%3A", ":").replace("%2C", ",");
+        assert!(data.contains("\"operation\":\"update\""), "{}", data);
+        assert!(data.contains("\"id\":\"A1\""), "{}", data);
+    }
+
+    #[test]
+    fn test_script_shows_urls_and_applescript() {
+        let backend = ThingsUrlBackend::new(Box::new(crate::things::ThingsBackend), "s3cret");
+        let script = backend
+            .script(&[
+                Op::Defer { id: "A1".to_string() },
+                Op::Delete { id: "B2".to_string() },
+            ])
+            .unwrap();
+        assert!(script.starts_with("open -g 'things:///json?auth-token=***&data="));
+        assert!(!script.contains("s3cret"));
+        assert!(script.contains(r#"set theTodo to to do id "B2""#));
+        assert!(!script.contains(r#"to do id "A1""#));
+    }
+
+    #[test]
+    fn test_completing_in_progress_todos_drops_the_tag() {
+        let backend = ThingsUrlBackend::new(Box::new(crate::things::ThingsBackend), "s3cret");
+        let in_progress = Op::Complete {
+            id: "A1".to_string(),
+            tags: vec!["errand".to_string(), config::get().tags.in_progress.clone()],
+        };
+        assert_eq!(update_json(&in_progress), None);
+
+        let script = backend.script(&[in_progress]).unwrap();
+        assert!(!script.contains("things:///json"), "{}", script);
+        assert!(script.contains(r#"set theTodo to to do id "A1""#), "{}", script);
+        assert!(script.contains(r#"{"in-progress"}"#), "{}", script);
+        assert!(script.contains("set status of theTodo to completed"), "{}", script);
+    }
+}
@@ -1,25 +1,202 @@
-#[derive(Debug, Clone)]
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Todo {
+    pub id: String,
     pub name: String,
-    pub tags: String,
+    pub tags: Vec<String>,
     pub is_completed: bool,
+    /// Position in the list, from 1; 0 if it can't be picked by number.
     pub index: usize,
     pub identifier: String,
+    pub list: String,
+    pub when: Option<NaiveDate>,
+    pub deadline: Option<NaiveDate>,
+    pub created: Option<NaiveDate>,
+    pub completed_on: Option<NaiveDate>,
+    /// The project the todo belongs to, wherever it's listed.
+    pub project: Option<String>,
+    pub notes: String,
+}
+
+/// When a new todo is scheduled for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Date(NaiveDate),
+    Anytime,
+    Someday,
+}
+
+/// A todo to be created; see `quickadd::parse` for the syntax that
+/// fills one in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewTodo {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub when: Option<When>,
+    pub deadline: Option<NaiveDate>,
+    pub notes: String,
+    pub checklist: Vec<String>,
+    /// Heading within the project the todo is added to.
+    pub heading: Option<String>,
+}
+
+impl NewTodo {
+    /// A todo called `name`, with nothing else set.
+    pub fn new(name: &str) -> Self {
+        NewTodo {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Splits a Things3 tag string ("errand, home") into tag names.
+pub fn parse_tag_names(tag_names: &str) -> Vec<String> {
+    tag_names
+        .split(',')
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Parses a time-estimate tag such as `15m`, `2hr` or `1d` into seconds.
+pub fn parse_time_seconds(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    let num_end = s.find(|c: char| !c.is_ascii_digit())?;
+    if num_end == 0 {
+        return None;
+    }
+    let num: u64 = s[..num_end].parse().ok()?;
+    let unit = s[num_end..].trim().to_lowercase();
+    match unit.as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(num),
+        "m" | "min" | "mins" | "minute" | "minutes" => num.checked_mul(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => num.checked_mul(3600),
+        "d" | "day" | "days" => num.checked_mul(86400),
+        _ => None,
+    }
 }
 
 impl Todo {
-    pub fn generate_base_identifier(name: &str) -> String {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.tags.retain(|t| t != tag);
+    }
+
+    /// The time estimate from the first time tag (see `parse_time_seconds`).
+    pub fn time_secs(&self) -> Option<u64> {
+        self.tags.iter().find_map(|tag| parse_time_seconds(tag))
+    }
+
+    /// Tags as Things3 shows them, e.g. "errand, home".
+    pub fn tag_text(&self) -> String {
+        self.tags.join(", ")
+    }
+
+    /// The first `width` letters or digits of `name`, upper-cased.
+    pub fn generate_base_identifier(name: &str, width: usize) -> String {
         let chars: Vec<char> = name
             .chars()
             .filter(|c| c.is_alphanumeric())
-            .take(3)
+            .take(width)
             .map(|c| c.to_uppercase().next().unwrap_or('_'))
             .collect();
 
         if chars.is_empty() {
-            "___".to_string()
+            "_".repeat(width)
         } else {
             chars.iter().collect()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag_names() {
+        assert_eq!(parse_tag_names("errand, home"), vec!["errand", "home"]);
+        assert_eq!(parse_tag_names(" 15m ,, work "), vec!["15m", "work"]);
+        assert!(parse_tag_names("").is_empty());
+    }
+
+    #[test]
+    fn test_has_tag_is_exact() {
+        let todo = Todo {
+            tags: parse_tag_names("not-in-progress, on-deck-later"),
+            ..Default::default()
+        };
+        assert!(!todo.has_tag("in-progress"));
+        assert!(!todo.has_tag("on-deck"));
+        assert!(todo.has_tag("on-deck-later"));
+    }
+
+    #[test]
+    fn test_remove_tag() {
+        let mut todo = Todo {
+            tags: parse_tag_names("in-progress, not-in-progress"),
+            ..Default::default()
+        };
+        todo.remove_tag("in-progress");
+        assert_eq!(todo.tag_text(), "not-in-progress");
+    }
+
+    #[test]
+    fn test_parse_time_seconds_minutes() {
+        assert_eq!(parse_time_seconds("5m"), Some(300));
+        assert_eq!(parse_time_seconds("2min"), Some(120));
+        assert_eq!(parse_time_seconds("1minute"), Some(60));
+        assert_eq!(parse_time_seconds("3minutes"), Some(180));
+    }
+
+    #[test]
+    fn test_parse_time_seconds_hours() {
+        assert_eq!(parse_time_seconds("1h"), Some(3600));
+        assert_eq!(parse_time_seconds("2hr"), Some(7200));
+        assert_eq!(parse_time_seconds("3hours"), Some(10800));
+    }
+
+    #[test]
+    fn test_parse_time_seconds_days() {
+        assert_eq!(parse_time_seconds("1d"), Some(86400));
+        assert_eq!(parse_time_seconds("2days"), Some(172800));
+    }
+
+    #[test]
+    fn test_parse_time_seconds_seconds() {
+        assert_eq!(parse_time_seconds("30s"), Some(30));
+        assert_eq!(parse_time_seconds("10sec"), Some(10));
+        assert_eq!(parse_time_seconds("60seconds"), Some(60));
+    }
+
+    #[test]
+    fn test_parse_time_seconds_invalid() {
+        assert_eq!(parse_time_seconds(""), None);
+        assert_eq!(parse_time_seconds("abc"), None);
+        assert_eq!(parse_time_seconds("5x"), None);
+        assert_eq!(parse_time_seconds("  "), None);
+        assert_eq!(parse_time_seconds("99999999999999999d"), None);
+        assert_eq!(parse_time_seconds("99999999999999999999s"), None);
+    }
+
+    #[test]
+    fn test_time_secs_uses_whole_tags() {
+        let mut todo = Todo {
+            tags: parse_tag_names("5m-ish, 15m"),
+            ..Default::default()
+        };
+        assert_eq!(todo.time_secs(), Some(900));
+        todo.tags = parse_tag_names("errand");
+        assert_eq!(todo.time_secs(), None);
+    }
+}